@@ -0,0 +1,55 @@
+#[macro_use]
+extern crate criterion;
+extern crate emul8;
+
+use criterion::Criterion;
+use emul8::internals::processor::Processor;
+use emul8::internals::variants::Variant;
+
+// A tight loop of cheap instructions, so the decoding overhead dominates
+const LOOP: [u8; 12] = [
+    0x60, 0x12, // LD V0, 0x12
+    0xA3, 0x00, // LDI 0x300
+    0x30, 0x12, // SE V0, 0x12
+    0x61, 0x01, // LD V1, 0x1 (skipped)
+    0x82, 0x34, // ADD V2, V3
+    0x12, 0x00, // JMP 0x200
+];
+
+// How many instructions are executed per iteration, roughly one frame at a high speed setting
+const INSTRUCTIONS: usize = 1000;
+
+fn processor() -> Processor {
+    let mut processor = Processor::new(Variant::Chip8.instruction_set());
+    processor.memory.ram[0x200..0x200 + LOOP.len()].copy_from_slice(&LOOP);
+    processor.memory.registers.pc = 0x200;
+    processor
+}
+
+fn bench_tick(c: &mut Criterion) {
+    let mut cached = processor();
+    c.bench_function("tick (decode cache)", move |b| {
+        b.iter(|| {
+            for _ in 0..INSTRUCTIONS {
+                cached.tick().unwrap();
+            }
+        })
+    });
+
+    // What tick did before the cache: Decode (and box) every single instruction again
+    let mut uncached = processor();
+    c.bench_function("tick (decode every instruction)", move |b| {
+        b.iter(|| {
+            for _ in 0..INSTRUCTIONS {
+                let op = uncached.decode_at(uncached.memory.registers.pc);
+                if !op.modified_pc() {
+                    uncached.memory.registers.pc += op.size();
+                }
+                op.execute(&mut uncached).unwrap();
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_tick);
+criterion_main!(benches);
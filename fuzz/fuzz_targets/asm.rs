@@ -0,0 +1,14 @@
+#![no_main]
+use emul8::internals::assembler;
+use libfuzzer_sys::fuzz_target;
+
+// Syntax errors, unknown labels and the like have to be reported, not panic
+fuzz_target!(|source: &str| {
+    if let Ok(program) = assembler::parse(source) {
+        if let Ok(instructions) = program.link(0x200) {
+            for instruction in instructions {
+                instruction.encode_bytes();
+            }
+        }
+    }
+});
@@ -0,0 +1,40 @@
+#![no_main]
+use emul8::internals::assembler;
+use emul8::internals::disassembler::Linear;
+use emul8::internals::opcode::InvalidOpcode;
+use emul8::internals::processor::Processor;
+use emul8::internals::variants::Variant;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte selects the variant, the rest is the program. Everything the disassembler
+// understands has to assemble to the same bytes again.
+fuzz_target!(|data: &[u8]| {
+    let (variant, program) = match data.split_first() {
+        Some((variant, program)) => (variant, program),
+        None => return,
+    };
+    let variant = match variant % 3 {
+        0 => Variant::Chip8,
+        1 => Variant::SuperChip,
+        _ => Variant::XoChip,
+    };
+    let mut processor = Processor::new(variant.instruction_set());
+    let len = std::cmp::min(program.len(), processor.memory.ram.len() - 0x200);
+    processor.memory.load(&program[..len], 0x200);
+
+    for (addr, _, op) in Linear::new(&mut processor, 0x200) {
+        if addr as usize >= 0x200 + len {
+            break;
+        }
+
+        let text = op.to_string();
+        if !op.is::<InvalidOpcode>() {
+            assert_eq!(
+                assembler::assemble(&text, 0x200),
+                Ok(op.encode()),
+                "{}",
+                text
+            );
+        }
+    }
+});
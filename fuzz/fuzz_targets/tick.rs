@@ -0,0 +1,51 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use emul8::internals::processor::{Processor, TICKS_PER_FRAME};
+use emul8::internals::variants::Variant;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    variant: u8,
+    // Loaded at 0x200 like a ROM, the rest of the memory keeps the fonts
+    ram: Vec<u8>,
+    // One bit per key, changing every frame
+    keys: Vec<u16>,
+    // The state the registers start in, so the program doesn't have to set them up itself
+    v: [u8; 16],
+    i: u16,
+    frames: u8,
+}
+
+fuzz_target!(|input: Input| {
+    let variant = match input.variant % 3 {
+        0 => Variant::Chip8,
+        1 => Variant::SuperChip,
+        _ => Variant::XoChip,
+    };
+    let mut processor = Processor::new(variant.instruction_set());
+    processor.keyboard.line_input = false;
+    processor.reseed(0);
+    let len = std::cmp::min(input.ram.len(), processor.memory.ram.len() - 0x200);
+    processor.memory.load(&input.ram[..len], 0x200);
+    processor.clear_decode_cache();
+    processor.memory.registers.pc = 0x200;
+    processor.memory.registers.v = input.v;
+    processor.memory.registers.i = input.i;
+
+    for frame in 0..input.frames as usize {
+        let keys = input.keys.get(frame).copied().unwrap_or_default();
+        for (key, pressed) in processor.keyboard.keys.iter_mut().enumerate() {
+            *pressed = keys & (1 << key) != 0;
+        }
+
+        for _ in 0..TICKS_PER_FRAME {
+            // Errors of the program end it, the emulator itself must not panic
+            if processor.tick().is_err() {
+                return;
+            }
+        }
+        processor.tick_timers();
+        processor.display.take_dirty_rows();
+    }
+});
@@ -0,0 +1,147 @@
+extern crate clap;
+extern crate emul8;
+use clap::{App, Arg, ArgGroup};
+use emul8::frontend::regression::{self, Expectation, TestCase};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let args = App::new("CHIP-8 Test Runner")
+        .version("0.1")
+        .author("Marc Streckfuß <marc.streckfuss@gmail.com>")
+        .about("Runs CHIP-8 programs headless and compares their final screen with a known good one")
+        .arg(Arg::with_name("manifest")
+            .index(1)
+            .multiple(true)
+            .value_name("MANIFEST")
+            .help("Files listing one test per line: The ROM followed by options like frames=N, expect=screen.pbm, hash=HEX, input=keys.c8in, variant=schip, sprite-edges=wrap or timing=vip")
+        )
+        .arg(Arg::with_name("rom")
+            .long("rom")
+            .value_name("FILE")
+            .help("Runs a single ROM instead of a manifest")
+        )
+        .group(ArgGroup::with_name("tests")
+            .args(&["manifest", "rom"])
+            .required(true)
+        )
+        .arg(Arg::with_name("frames")
+            .long("frames")
+            .value_name("N")
+            .requires("rom")
+            .help("How many frames the ROM runs before its screen is compared (defaults to 60)")
+        )
+        .arg(Arg::with_name("input")
+            .long("input")
+            .value_name("FILE")
+            .requires("rom")
+            .help("Feeds the keypad from an input replay recorded by emul8 --record-input")
+        )
+        .arg(Arg::with_name("expect")
+            .long("expect")
+            .value_name("FILE")
+            .requires("rom")
+            .conflicts_with("hash")
+            .help("A PBM screenshot of how the screen has to look like, as saved by emul8 --screenshot-on-exit")
+        )
+        .arg(Arg::with_name("hash")
+            .long("hash")
+            .value_name("HEX")
+            .requires("rom")
+            .help("The checksum the screen has to have, without either the test fails and reports it")
+        )
+        .arg(Arg::with_name("variant")
+            .long("variant")
+            .takes_value(true)
+            .requires("rom")
+            .possible_values(&["chip8", "schip", "xochip"])
+            .help("The CHIP-8 dialect of the ROM (defaults to chip8)")
+        )
+        .arg(Arg::with_name("sprite-edges")
+            .long("sprite-edges")
+            .takes_value(true)
+            .requires("rom")
+            .possible_values(&["clip", "wrap"])
+            .help("What happens to sprites drawn past the edges of the screen (defaults to clip)")
+        )
+        .arg(Arg::with_name("timing")
+            .long("timing")
+            .takes_value(true)
+            .requires("rom")
+            .possible_values(&["fixed", "vip"])
+            .help("How many instructions run per frame, see emul8 --timing (defaults to fixed)")
+        )
+        .arg(Arg::with_name("format")
+            .long("format")
+            .help("TAP for humans and most test harnesses, JUnit XML for CI servers")
+            .possible_values(&["tap", "junit"])
+            .default_value("tap")
+        )
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Writes the report into a file instead of stdout")
+        )
+        .get_matches();
+
+    let (suite, tests) = match args.value_of("rom") {
+        Some(rom) => {
+            let mut test = TestCase::new(PathBuf::from(rom));
+            if let Some(variant) = args.value_of("variant") {
+                test.variant = variant.parse().unwrap();
+            }
+            if let Some(sprite_edges) = args.value_of("sprite-edges") {
+                test.sprite_edges = sprite_edges.parse().unwrap();
+            }
+            if let Some(timing) = args.value_of("timing") {
+                test.timing = timing.parse().unwrap();
+            }
+            if let Some(frames) = args.value_of("frames") {
+                test.frames = frames.parse().expect("Invalid number of frames");
+            }
+            test.input = args.value_of("input").map(PathBuf::from);
+            if let Some(expect) = args.value_of("expect") {
+                test.expectation = Expectation::Pbm(PathBuf::from(expect));
+            } else if let Some(hash) = args.value_of("hash") {
+                test.expectation =
+                    Expectation::Hash(u64::from_str_radix(hash, 16).expect("Invalid hash"));
+            }
+            (rom.to_string(), vec![test])
+        }
+        None => {
+            let manifests: Vec<&str> = args.values_of("manifest").unwrap().collect();
+            let mut tests = Vec::new();
+            for manifest in manifests.iter() {
+                tests.extend(
+                    regression::read_manifest(Path::new(manifest))
+                        .unwrap_or_else(|error| panic!("{}", error)),
+                );
+            }
+            (manifests.join(" "), tests)
+        }
+    };
+
+    // The panics of the emulator end up in the report, they shouldn't clutter the terminal
+    std::panic::set_hook(Box::new(|_| {}));
+    let outcomes: Vec<_> = tests.iter().map(regression::run).collect();
+    let _ = std::panic::take_hook();
+
+    let mut out: Box<dyn Write> = match args.value_of("output") {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).expect("Unable to create the report"),
+        )),
+        None => Box::new(io::stdout()),
+    };
+    match args.value_of("format").unwrap() {
+        "junit" => regression::write_junit(&mut out, &suite, &outcomes),
+        _ => regression::write_tap(&mut out, &outcomes),
+    }
+    .and_then(|_| out.flush())
+    .expect("Error when writing the report");
+
+    if outcomes.iter().any(|outcome| !outcome.passed()) {
+        std::process::exit(1);
+    }
+}
@@ -1,3 +1,5 @@
+// The compiler is still a stub, see the unimplemented!() in main
+#![allow(unused_variables, unreachable_code, clippy::needless_late_init)]
 extern crate clap;
 extern crate emul8;
 extern crate pest;
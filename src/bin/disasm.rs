@@ -1,7 +1,6 @@
 extern crate clap;
 extern crate emul8;
 use clap::{App, Arg};
-use emul8::internals::opcode::Opcode;
 use emul8::internals::*;
 
 fn main() {
@@ -33,6 +32,12 @@ fn main() {
             .multiple(true)
             .help("Sets the level of verbosity")
         )
+        .arg(Arg::with_name("variant")
+            .long("variant")
+            .help("The CHIP-8 dialect the binary is written in, which determines the set of known opcodes.")
+            .possible_values(&["chip8", "schip", "xochip"])
+            .default_value("chip8")
+        )
         .arg(Arg::with_name("mode")
             .short("m")
             .long("mode")
@@ -75,11 +80,8 @@ fn main() {
     let ignore_errors = !args.is_present("dont-ignore-errors");
     let stop_zero = !args.is_present("dont-stop-on-zerobytes");
 
-    let mut processor = processor::Processor {
-        display: display::Display::default(),
-        memory: memory::Memory::default(),
-        keyboard: keyboard::Keyboard::default(),
-    };
+    let variant: variants::Variant = args.value_of("variant").unwrap().parse().unwrap();
+    let mut processor = processor::Processor::new(variant.instruction_set());
 
     processor
         .memory
@@ -101,46 +103,36 @@ fn main() {
         println!("<Memory Address>\t<Opcodes>\t<Assembler>");
     }
 
-    while processor.memory.registers.pc <= 4094 {
-        let opcode = processor.fetch_opcode();
-        let op: Box<dyn Opcode> = processor.decode_opcode(opcode);
+    for (pc, opcode, op) in disassembler::Linear::new(&mut processor, ep) {
         //if opcode.0 != 0 || opcode.1 != 0 || verbosity == 2
-        if op.to_string() != "INVALID" {
+        if !matches!(
+            op.instruction(),
+            instruction::Instruction::InvalidOpcode { .. }
+        ) {
             if !canonical {
-                println!(
-                    "{:#X}\t\t\t{:#X} {:#X}\t{}",
-                    processor.memory.registers.pc, opcode.0, opcode.1, op
-                );
+                println!("{:#X}\t\t\t{:#X} {:#X}\t{}", pc, opcode.0, opcode.1, op);
             } else {
-                println!("{} ; {:#X}", op, processor.memory.registers.pc);
+                println!("{} ; {:#X}", op, pc);
             }
         } else if opcode.0 == 0 && opcode.1 == 0 {
             if stop_zero {
                 panic!("Stopping disassembly as the first 0x00 0x00 data has been reached");
             } else if verbosity == 2 {
                 if !canonical {
-                    println!(
-                        "{:#X}\t\t\t{:#X} {:#X}\t{}",
-                        processor.memory.registers.pc, opcode.0, opcode.1, op
-                    );
+                    println!("{:#X}\t\t\t{:#X} {:#X}\t{}", pc, opcode.0, opcode.1, op);
                 } else {
-                    println!("{} ; {:#X}", op, processor.memory.registers.pc);
+                    println!("{} ; {:#X}", op, pc);
                 }
             }
         } else if !ignore_errors {
             panic!("Got an Invalid Opcode, probably reached the end of the file or a data sector.")
         } else if verbosity == 2 {
             if !canonical {
-                println!(
-                    "{:#X}\t\t\t{:#X} {:#X}\t{}",
-                    processor.memory.registers.pc, opcode.0, opcode.1, op
-                );
+                println!("{:#X}\t\t\t{:#X} {:#X}\t{}", pc, opcode.0, opcode.1, op);
             } else {
-                println!("{} ; {:#X}", op, processor.memory.registers.pc);
+                println!("{} ; {:#X}", op, pc);
             }
         }
-
-        processor.memory.registers.pc += 2;
     }
 
     /*loop {
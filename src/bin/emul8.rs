@@ -1,9 +1,19 @@
 extern crate clap;
 extern crate emul8;
 use clap::{App, Arg};
+use emul8::frontend::recorder::{self, Recorder};
+use emul8::frontend::terminal;
+use emul8::frontend::wav::WavSink;
 use emul8::internals::opcode::Opcode;
 use emul8::internals::*;
-use std::io::stdin;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{stdin, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// How many instructions the debugger can step back
+const REWIND_DEPTH: usize = 100_000;
 
 fn main() {
     let args = App::new("CHIP-8 Emulator")
@@ -27,6 +37,109 @@ fn main() {
             .short("v")
             .multiple(true)
             .help("Sets the level of verbosity"))
+        .arg(Arg::with_name("variant")
+            .long("variant")
+            .help("The CHIP-8 dialect to emulate. schip enables the SUPER-CHIP 1.1 extensions (hires mode, scrolling, big sprites), xochip additionally enables 64 KiB memory, bitplanes and audio patterns.")
+            .possible_values(&["chip8", "schip", "xochip"])
+            .default_value("chip8"))
+        .arg(Arg::with_name("sprite-edges")
+            .long("sprite-edges")
+            .help("Whether the parts of sprites drawn past the edges of the screen are clipped (like the original interpreter) or wrap around to the opposite edge.")
+            .possible_values(&["clip", "wrap"])
+            .default_value("clip"))
+        .arg(Arg::with_name("addressing")
+            .long("addressing")
+            .help("How addresses past the end of the memory are handled: strict stops the program, wrap cuts them to 12 bits like the original hardware and extended to the 16 bits of XO-CHIP. Defaults to extended for xochip and wrap otherwise.")
+            .possible_values(&["strict", "wrap", "extended"])
+            .takes_value(true))
+        .arg(Arg::with_name("font")
+            .long("font")
+            .help("The hex digits of the interpreter of the COSMAC VIP, DREAM 6800, ETI-660, SUPER-CHIP or the ones most emulators use (standard)")
+            .possible_values(&["standard", "vip", "dream6800", "eti660", "schip"])
+            .default_value("standard"))
+        .arg(Arg::with_name("font-file")
+            .long("font-file")
+            .value_name("FILE")
+            .conflicts_with("font")
+            .help("Load the font from FILE instead, which contains the 16 digits with 5 bytes each, optionally followed by the 16 big SUPER-CHIP digits with 10 bytes each"))
+        .arg(Arg::with_name("font-addr")
+            .long("font-addr")
+            .value_name("ADDR")
+            .help("Where the font is stored (the big font follows it), e.g. 0x0 like on the COSMAC VIP")
+            .default_value("0x50"))
+        .arg(Arg::with_name("layout")
+            .long("layout")
+            .help("Where the stack, V0-VF and the display live: only inside the emulator (flat) or also in the RAM at 0xEA0-0xFFF like on the COSMAC VIP (vip, only for chip8, implies --stack-depth 12 and allows at most 24)")
+            .possible_values(&["flat", "vip"])
+            .default_value("flat"))
+        .arg(Arg::with_name("timing")
+            .long("timing")
+            .help("How many instructions run per frame: always the same number (fixed) or as many as the COSMAC VIP managed, which depends on the instructions and waits for the next frame after drawing (vip)")
+            .possible_values(&["fixed", "vip"])
+            .default_value("fixed"))
+        .arg(Arg::with_name("stack-depth")
+            .long("stack-depth")
+            .value_name("N")
+            .help("How many subroutine calls can be nested before the stack overflows, e.g. 12 like on the COSMAC VIP. Defaults to 32 for schip and xochip and 16 otherwise."))
+        .arg(Arg::with_name("foreground")
+            .long("foreground")
+            .help("The colour of set pixels in the terminal, either a name (e.g. green, dark_grey) or a hex value like #33FF66")
+            .default_value("white"))
+        .arg(Arg::with_name("background")
+            .long("background")
+            .help("The colour of unset pixels in the terminal, see --foreground")
+            .default_value("black"))
+        .arg(Arg::with_name("headless")
+            .long("headless")
+            .help("Run the program without any output or input, e.g. to take a screenshot in CI"))
+        .arg(Arg::with_name("frames")
+            .long("frames")
+            .value_name("N")
+            .help("Stop after running N frames (at 60 frames per second)"))
+        .arg(Arg::with_name("screenshot-on-exit")
+            .long("screenshot-on-exit")
+            .value_name("FILE")
+            .help("Save the screen to FILE when the emulator stops. Written as PBM if FILE ends with .pbm, otherwise as PNG"))
+        .arg(Arg::with_name("record")
+            .long("record")
+            .value_name("FILE")
+            .help("Record every frame that differs from the previous one (implies --headless). Written as animated GIF if FILE ends with .gif, otherwise as numbered images like FILE-000042.png"))
+        .arg(Arg::with_name("audio-out")
+            .long("audio-out")
+            .value_name("FILE")
+            .help("Write the sound (a square wave while the sound timer is active) into the WAV file FILE"))
+        .arg(Arg::with_name("scale")
+            .long("scale")
+            .help("The size of a single CHIP-8 pixel in screenshots and recordings")
+            .default_value("8"))
+        .arg(Arg::with_name("palette")
+            .long("palette")
+            .help("The colours of screenshots and recordings as comma separated hex values: unset pixels, pixels set in the first, the second and both bitplanes")
+            .default_value("#000000,#FFFFFF,#AAAAAA,#555555"))
+        .arg(Arg::with_name("load-state")
+            .long("load-state")
+            .value_name("FILE")
+            .help("Resume from a save state (made by the debugger's save command) instead of starting the program"))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .value_name("N")
+            .conflicts_with("replay-input")
+            .help("Seed the random number source, so RND always produces the same numbers"))
+        .arg(Arg::with_name("random")
+            .long("random")
+            .possible_values(&["std", "vip"])
+            .default_value("std")
+            .help("Where RND gets its numbers from: a uniform random number generator or the routine of the COSMAC VIP interpreter. A replay uses the one it has been recorded with."))
+        .arg(Arg::with_name("record-input")
+            .long("record-input")
+            .value_name("FILE")
+            .conflicts_with("debug")
+            .help("Record every key press and release together with the random seed, so the session can be replayed with --replay-input"))
+        .arg(Arg::with_name("replay-input")
+            .long("replay-input")
+            .value_name("FILE")
+            .conflicts_with("debug")
+            .help("Feed the keypad from a file made by --record-input and check that the memory ends up the same"))
         .arg(Arg::with_name("debug")
             .short("D")
             .long("debug")
@@ -64,15 +177,63 @@ fn main() {
     //let ignore_errors = !args.is_present("dont-ignore-errors");
     //let stop_zero = !args.is_present("dont-stop-on-zerobytes");
 
-    let mut processor = processor::Processor {
-        memory: memory::Memory::default(),
-        keyboard: keyboard::Keyboard::default(),
-        display: display::Display::default(),
+    let variant: variants::Variant = args.value_of("variant").unwrap().parse().unwrap();
+    let mut processor = processor::Processor::new(variant.instruction_set());
+    processor.display.sprite_edges = args.value_of("sprite-edges").unwrap().parse().unwrap();
+    if let Some(addressing) = args.value_of("addressing") {
+        processor.memory.addressing = addressing.parse().unwrap();
+    }
+    let font = match args.value_of("font-file") {
+        Some(path) => font::Font::read(Path::new(path)).unwrap_or_else(|error| panic!("{}", error)),
+        None => font::Font::builtin(args.value_of("font").unwrap().parse().unwrap()),
     };
+    let font_addr = parse_address(args.value_of("font-addr").unwrap());
+    if processor.set_font(&font, font_addr).is_err() {
+        eprintln!(
+            "The fonts take {} bytes, which don't fit into the memory at {:#X}",
+            font::FONT_LEN + font::BIG_FONT_LEN,
+            font_addr
+        );
+        std::process::exit(1);
+    }
+    processor.timing = args.value_of("timing").unwrap().parse().unwrap();
+    processor.layout = args.value_of("layout").unwrap().parse().unwrap();
+    if processor.layout == layout::Layout::Vip {
+        if variant != variants::Variant::Chip8 {
+            eprintln!(
+                "The VIP memory layout is only available for --variant chip8, not {}",
+                args.value_of("variant").unwrap()
+            );
+            std::process::exit(1);
+        }
+        processor.memory.stack = vec![0; 12];
+    }
+    if let Some(depth) = args.value_of("stack-depth") {
+        // The stack pointer is a signed byte
+        let depth = depth
+            .parse::<i8>()
+            .ok()
+            .filter(|&depth| depth > 0)
+            .expect("The stack depth has to be between 1 and 127");
+        processor.memory.stack = vec![0; depth as usize];
+    }
+    if processor.layout == layout::Layout::Vip
+        && processor.memory.stack.len() > layout::VIP_STACK_DEPTH
+    {
+        eprintln!(
+            "The VIP memory layout only has room for a stack depth of {}",
+            layout::VIP_STACK_DEPTH
+        );
+        std::process::exit(1);
+    }
+    let random: random::RandomMode = args.value_of("random").unwrap().parse().unwrap();
+    processor.rng = random.source(processor.seed);
+    if let Some(seed) = args.value_of("seed") {
+        processor.reseed(seed.parse().expect("Unable to parse the seed"));
+    }
 
     processor
-        .memory
-        .load_from_file(args.value_of("infile").unwrap(), lp)
+        .load_rom(args.value_of("infile").unwrap(), lp)
         .unwrap_or_else(|_| {
             panic!(
                 "Failed to load the input file {}",
@@ -81,6 +242,11 @@ fn main() {
         });
     processor.memory.registers.pc = ep;
 
+    if let Some(path) = args.value_of("load-state") {
+        load_state(&mut processor, path)
+            .unwrap_or_else(|e| panic!("Unable to load the save state {}: {}", path, e));
+    }
+
     if verbosity > 0 {
         println!(
             "Emulating file {} starting at {:#X}, loaded at {:#X}",
@@ -90,18 +256,61 @@ fn main() {
         );
     }
 
-    // @TODO: breakpoints!!!
+    let frames = args.value_of("frames").map(|frames| {
+        frames
+            .parse::<usize>()
+            .expect("Unable to parse the frames value")
+    });
+    let scale = args
+        .value_of("scale")
+        .unwrap()
+        .parse::<usize>()
+        .expect("Unable to parse the scale value");
+    let palette: display::Palette = args.value_of("palette").unwrap().parse().unwrap();
+
+    let mut beeper = args.value_of("audio-out").map(|path| {
+        let sink = WavSink::create(path)
+            .unwrap_or_else(|e| panic!("Unable to create the audio file {}: {}", path, e));
+        audio::Beeper::new(Box::new(sink))
+    });
+
+    let mut replay = args.value_of("replay-input").map(|path| {
+        let file = File::open(path)
+            .unwrap_or_else(|e| panic!("Unable to open the input replay {}: {}", path, e));
+        replay::InputReplay::read(BufReader::new(file))
+            .unwrap_or_else(|e| panic!("Unable to read the input replay {}: {}", path, e))
+    });
+    if let Some(replay) = &replay {
+        processor.rng = replay.random.source(replay.seed);
+        processor.reseed(replay.seed);
+        // The keys are only known per cycle, so LDVxK must not block on stdin
+        processor.keyboard.line_input = false;
+    }
+
+    let mut input_recorder = args.value_of("record-input").map(|path| {
+        let file = File::create(path)
+            .unwrap_or_else(|e| panic!("Unable to create the input recording {}: {}", path, e));
+        replay::InputRecorder::new(BufWriter::new(file), processor.seed, random)
+            .unwrap_or_else(|e| panic!("Unable to write the input recording {}: {}", path, e))
+    });
+
+    // The error the program stopped with, reported once the terminal is restored
+    let mut program_error = None;
+
     if args.is_present("debug") {
         let mut paused = true; // Start the debugger paused.
+        let mut breakpoints = HashSet::new();
+        let mut history = rewind::History::new(REWIND_DEPTH);
 
-        loop {
-            if processor.memory.registers.pc > 4094 {
-                panic!("Exceeded Memory at pc={:#X}", processor.memory.registers.pc);
+        'debugger: loop {
+            if processor.halted {
+                println!("The program exited. Step back with rs or rc, or quit with q.");
+                paused = true;
             }
 
             if paused {
                 let opcode = processor.fetch_opcode();
-                let op: Box<dyn Opcode> = processor.decode_opcode(opcode);
+                let op: Box<dyn Opcode> = processor.decode_at(processor.memory.registers.pc);
                 if verbosity > 0 {
                     println!("<Memory Address>\t<Opcodes>\t<Assembler>");
                 }
@@ -117,33 +326,94 @@ fn main() {
                         .expect("Error when reading input");
 
                     if cmd_line.starts_with("p ") {
-                        let addr_s = cmd_line.trim_start_matches("p ").trim();
-
-                        let addr = if addr_s.starts_with("0x") {
-                            u16::from_str_radix(addr_s.trim_start_matches("0x"), 16).unwrap()
-                        } else {
-                            u16::from_str_radix(addr_s, 10).unwrap()
-                        };
+                        let addr = parse_address(cmd_line.trim_start_matches("p "));
 
                         let opc = processor.memory.read_two_u8(addr);
-                        let opd = processor.decode_opcode(opc);
+                        let opd = processor.decode_at(addr);
 
                         if verbosity > 0 {
                             println!("<Memory Address>\t<Opcodes>\t<Assembler>");
                         }
                         println!("{:#X}\t\t\t{:#X} {:#X}\t{}", addr, opc.0, opc.1, opd);
+                    } else if cmd_line.starts_with("m ") {
+                        // m <addr> [len] dumps the memory, 16 bytes per line
+                        let mut fields = cmd_line.trim_start_matches("m ").split_whitespace();
+                        let addr = parse_address(fields.next().unwrap_or("0")) as usize;
+                        let len = fields
+                            .next()
+                            .map_or(0x40, |len| parse_address(len) as usize);
+                        let end = std::cmp::min(addr + len, processor.memory.ram.len());
+                        for line in (addr..end).step_by(16) {
+                            let bytes = &processor.memory.ram[line..std::cmp::min(line + 16, end)];
+                            let hex: Vec<String> =
+                                bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                            println!("{:#06X}\t{}", line, hex.join(" "));
+                        }
+                    } else if cmd_line.starts_with("b ") {
+                        let addr = parse_address(cmd_line.trim_start_matches("b "));
+                        if breakpoints.remove(&addr) {
+                            println!("Removed the breakpoint at {:#X}", addr);
+                        } else {
+                            breakpoints.insert(addr);
+                            println!("Set a breakpoint at {:#X}", addr);
+                        }
+                    } else if cmd_line.starts_with("save ") {
+                        let slot = cmd_line.trim_start_matches("save ").trim();
+                        let path = state_path(args.value_of("infile").unwrap(), slot);
+                        let mut out = BufWriter::new(File::create(&path).unwrap_or_else(|_| {
+                            panic!("Unable to create the save state {}", path)
+                        }));
+                        processor
+                            .save_state(&mut out)
+                            .and_then(|_| out.flush())
+                            .unwrap_or_else(|e| {
+                                panic!("Unable to write the save state {}: {}", path, e)
+                            });
+                        println!("Saved the state to {}", path);
+                    } else if cmd_line.starts_with("load ") {
+                        let slot = cmd_line.trim_start_matches("load ").trim();
+                        let path = state_path(args.value_of("infile").unwrap(), slot);
+                        match load_state(&mut processor, &path) {
+                            Ok(_) => break,
+                            Err(e) => println!("Unable to load the save state {}: {}", path, e),
+                        }
+                    } else if cmd_line.starts_with("shot ") {
+                        let path = cmd_line.trim_start_matches("shot ").trim();
+                        save_screenshot(&processor.display, path, scale, &palette);
+                        println!("Saved the screen to {}", path);
                     } else {
                         match cmd_line.trim() {
                             "s" => {
-                                op.execute(&mut processor);
-                                if !op.modified_pc() {
-                                    processor.memory.registers.pc += 2;
+                                match history.tick(&mut processor) {
+                                    Ok(()) => processor.end_full_frame(),
+                                    Err(error) => println!("{}", error),
+                                }
+                                break;
+                            }
+                            "rs" => {
+                                if history.step_back(&mut processor) {
+                                    break;
+                                }
+                                println!("Cannot step back any further.");
+                            }
+                            "rc" => {
+                                if !history.step_back_to(&mut processor, &breakpoints) {
+                                    println!("Reached the oldest remembered instruction.");
                                 }
                                 break;
                             }
-                            "q" => return,
+                            "q" => break 'debugger,
                             "r" => println!("{}", processor.memory.registers),
                             "st" => println!("{:x?}", processor.memory.stack),
+                            "bt" => {
+                                println!("#0 {:#X} (pc)", processor.memory.registers.pc);
+                                for (frame, addr) in
+                                    processor.memory.backtrace().into_iter().enumerate()
+                                {
+                                    let call = processor.decode_cached(addr);
+                                    println!("#{} {:#X} {}", frame + 1, addr, call);
+                                }
+                            }
                             "d" => println!("{}", processor.display),
                             "i" => {
                                 if verbosity > 0 {
@@ -165,12 +435,144 @@ fn main() {
                         }
                     }
                 }
+            } else if let Err(error) = history.tick(&mut processor) {
+                println!("{}", error);
+                paused = true;
             } else {
-                processor.tick();
+                processor.end_full_frame();
+                if breakpoints.contains(&processor.memory.registers.pc) {
+                    println!("Hit the breakpoint at {:#X}", processor.memory.registers.pc);
+                    paused = true;
+                }
             }
         }
+    } else if args.is_present("headless") || args.is_present("record") {
+        let mut recorder: Option<Box<dyn Recorder>> = args.value_of("record").map(|path| {
+            recorder::recorder_for(path, scale, &palette)
+                .unwrap_or_else(|e| panic!("Unable to record to {}: {}", path, e))
+        });
+        // Headless runs must never wait for the terminal, LDVxK waits for the keypad instead
+        processor.keyboard.line_input = false;
+
+        let mut frame = 0;
+        while !processor.halted && frames.is_none_or(|frames| frame < frames) {
+            if let Some(replay) = &mut replay {
+                if replay.is_finished(processor.cycles) {
+                    break;
+                }
+                replay.apply(processor.cycles, &mut processor.keyboard);
+            }
+
+            if let Some(input_recorder) = &mut input_recorder {
+                input_recorder
+                    .record(processor.cycles, &processor.keyboard)
+                    .expect("Error when recording the input");
+            }
+
+            if let Err(error) = run_frame(&mut processor, &mut beeper) {
+                program_error = Some(error);
+                break;
+            }
+            frame += 1;
+
+            if let Some(recorder) = &mut recorder {
+                recorder
+                    .capture(&processor.display)
+                    .expect("Error when recording the frame");
+            }
+        }
+
+        if let Some(recorder) = &mut recorder {
+            recorder
+                .finish()
+                .expect("Error when finishing the recording");
+        }
     } else {
-        unimplemented!()
+        let foreground = terminal::parse_color(args.value_of("foreground").unwrap()).unwrap();
+        let background = terminal::parse_color(args.value_of("background").unwrap()).unwrap();
+        let mut terminal = terminal::Terminal::new(foreground, background)
+            .expect("Unable to put the terminal into raw mode");
+        processor.keyboard.line_input = false;
+
+        let frame_duration = Duration::from_secs(1) / 60;
+        let mut frame = 0;
+        while !processor.halted && frames.is_none_or(|frames| frame < frames) {
+            let start = Instant::now();
+            // While replaying, the terminal is only polled for Escape
+            let mut ignored_keys = keyboard::Keyboard::default();
+            let keys = if replay.is_some() {
+                &mut ignored_keys
+            } else {
+                &mut processor.keyboard
+            };
+            if !terminal
+                .poll_keys(keys)
+                .expect("Error when reading keyboard input")
+            {
+                break;
+            }
+
+            if let Some(replay) = &mut replay {
+                if replay.is_finished(processor.cycles) {
+                    break;
+                }
+                replay.apply(processor.cycles, &mut processor.keyboard);
+            }
+
+            if let Some(input_recorder) = &mut input_recorder {
+                input_recorder
+                    .record(processor.cycles, &processor.keyboard)
+                    .expect("Error when recording the input");
+            }
+
+            if let Err(error) = run_frame(&mut processor, &mut beeper) {
+                program_error = Some(error);
+                break;
+            }
+            frame += 1;
+
+            terminal
+                .render(&mut processor.display)
+                .expect("Error when drawing to the terminal");
+            if let Some(rest) = frame_duration.checked_sub(start.elapsed()) {
+                std::thread::sleep(rest);
+            }
+        }
+    }
+
+    if let Some(beeper) = &mut beeper {
+        beeper
+            .finish()
+            .expect("Error when finishing the audio file");
+    }
+
+    if let Some(input_recorder) = &mut input_recorder {
+        input_recorder
+            .finish(processor.cycles, &processor.memory)
+            .expect("Error when finishing the input recording");
+    }
+
+    if let Some(replay) = &replay {
+        if !replay.is_finished(processor.cycles) {
+            println!(
+                "The replay stopped at cycle {} before its end at cycle {}",
+                processor.cycles, replay.end_cycle
+            );
+        } else {
+            match replay.verify(&processor.memory) {
+                Ok(()) => println!("Replay matches the recording"),
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+
+    if let Some(path) = args.value_of("screenshot-on-exit") {
+        save_screenshot(&processor.display, path, scale, &palette);
+    }
+
+    if let Some(error) = program_error {
+        eprintln!("{} (pc={:#X})", error, processor.memory.registers.pc);
+        std::process::exit(1);
     }
 
     /*while processor.memory.registers.pc <= 4094 {
@@ -214,3 +616,58 @@ fn main() {
     }
     dbg!(processor.memory);*/*/
 }
+
+// Executes one frame worth of instructions, produces its sound and counts the timers down
+fn run_frame(
+    processor: &mut processor::Processor,
+    beeper: &mut Option<audio::Beeper>,
+) -> Result<(), fault::Fault> {
+    processor.run_frame()?;
+
+    if let Some(beeper) = beeper {
+        beeper
+            .render_frame(processor.memory.registers.st)
+            .expect("Error when writing the audio");
+    }
+    processor.tick_timers();
+    Ok(())
+}
+
+// Writes a PBM if the file name ends with .pbm, otherwise a PNG
+fn save_screenshot(
+    display: &display::Display,
+    path: &str,
+    scale: usize,
+    palette: &display::Palette,
+) {
+    let mut out = BufWriter::new(
+        File::create(path).unwrap_or_else(|_| panic!("Unable to create the screenshot {}", path)),
+    );
+
+    if path.ends_with(".pbm") {
+        display.write_pbm(&mut out, scale)
+    } else {
+        display.write_png(&mut out, scale, palette)
+    }
+    .and_then(|_| out.flush())
+    .unwrap_or_else(|e| panic!("Unable to write the screenshot {}: {}", path, e));
+}
+
+// The file the debugger saves the state slot of a program to, e.g. game.ch8.state1
+fn state_path(infile: &str, slot: &str) -> String {
+    format!("{}.state{}", infile, slot)
+}
+
+fn load_state(processor: &mut processor::Processor, path: &str) -> std::io::Result<()> {
+    processor.load_state(&mut BufReader::new(File::open(path)?))
+}
+
+// Parses a hexadecimal address starting with 0x or a decimal one
+fn parse_address(addr_s: &str) -> u16 {
+    let addr_s = addr_s.trim();
+    if addr_s.starts_with("0x") {
+        u16::from_str_radix(addr_s.trim_start_matches("0x"), 16).unwrap()
+    } else {
+        addr_s.parse::<u16>().unwrap()
+    }
+}
@@ -0,0 +1,152 @@
+use crate::internals::display::{Display, Palette, HIRES_HEIGHT, HIRES_WIDTH};
+use gif::{Encoder, Frame, Repeat};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Records what has been drawn, frame by frame. Frames which are identical to the previous one are
+// skipped, so capture can simply be called for every 60 Hz frame.
+pub trait Recorder {
+    fn capture(&mut self, display: &Display) -> io::Result<()>;
+
+    // Has to be called after the last frame
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+// Chooses the recorder by the file name: An animated GIF for .gif, otherwise an image sequence
+pub fn recorder_for(path: &str, scale: usize, palette: &Palette) -> io::Result<Box<dyn Recorder>> {
+    if path.ends_with(".gif") {
+        let out = BufWriter::new(File::create(path)?);
+        Ok(Box::new(GifRecorder::new(out, scale, palette)?))
+    } else {
+        Ok(Box::new(ImageSequence::new(path, scale, palette)))
+    }
+}
+
+// Converts a number of 60 Hz frames to the hundredths of a second used by GIF
+fn centiseconds(frames: usize) -> usize {
+    (frames * 100 + 30) / 60
+}
+
+pub struct GifRecorder<W: Write> {
+    encoder: Encoder<W>,
+    scale: usize,
+    // The last captured image and the frame it has been captured at. It is only written once a
+    // different one is captured, because only then it is known how long it has been shown.
+    pending: Option<(Vec<u8>, usize)>,
+    frame: usize,
+}
+
+impl<W: Write> GifRecorder<W> {
+    // The GIF always has the size of the high resolution, so low resolution frames are scaled up
+    // twice as much
+    pub fn new(out: W, scale: usize, palette: &Palette) -> io::Result<GifRecorder<W>> {
+        let (width, height) = (HIRES_WIDTH * scale, HIRES_HEIGHT * scale);
+        let mut encoder = Encoder::new(out, width as u16, height as u16, &palette.colors.concat())
+            .map_err(io::Error::other)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        Ok(GifRecorder {
+            encoder,
+            scale,
+            pending: None,
+            frame: 0,
+        })
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        if let Some((image, start)) = self.pending.take() {
+            let frame = Frame {
+                width: (HIRES_WIDTH * self.scale) as u16,
+                height: (HIRES_HEIGHT * self.scale) as u16,
+                delay: (centiseconds(self.frame) - centiseconds(start)) as u16,
+                buffer: Cow::Owned(image),
+                ..Frame::default()
+            };
+            self.encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Recorder for GifRecorder<W> {
+    fn capture(&mut self, display: &Display) -> io::Result<()> {
+        let image = display.image(self.scale * HIRES_WIDTH / display.width());
+        if self.pending.as_ref().map(|(pending, _)| pending) != Some(&image) {
+            self.write_pending()?;
+            self.pending = Some((image, self.frame));
+        }
+
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.encoder.get_mut().flush()
+    }
+}
+
+// Writes every frame to its own file, numbered by the frame it has been captured at, e.g.
+// out-000042.png for out.png. Like screenshots, .pbm files are written as PBM, all others as PNG.
+pub struct ImageSequence {
+    path: String,
+    scale: usize,
+    palette: Palette,
+    last: Option<Vec<u8>>,
+    frame: usize,
+}
+
+impl ImageSequence {
+    pub fn new(path: &str, scale: usize, palette: &Palette) -> ImageSequence {
+        ImageSequence {
+            path: path.to_string(),
+            scale,
+            palette: *palette,
+            last: None,
+            frame: 0,
+        }
+    }
+}
+
+// Inserts the frame number between the file stem and the extension, leaving the directories alone
+fn frame_path(path: &str, frame: usize) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{:06}.{}", stem, frame, extension.to_string_lossy()),
+        None => format!("{}-{:06}", stem, frame),
+    };
+    path.with_file_name(name)
+}
+
+impl Recorder for ImageSequence {
+    fn capture(&mut self, display: &Display) -> io::Result<()> {
+        let image = display.image(1);
+        if self.last.as_ref() != Some(&image) {
+            let path = frame_path(&self.path, self.frame);
+            let mut out = BufWriter::new(File::create(&path)?);
+            if path.extension().is_some_and(|extension| extension == "pbm") {
+                display.write_pbm(&mut out, self.scale)?;
+            } else {
+                display.write_png(&mut out, self.scale, &self.palette)?;
+            }
+            out.flush()?;
+            self.last = Some(image);
+        }
+
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,103 @@
+use super::*;
+use std::fs;
+
+// A directory of its own for every test, as they run in parallel
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("emul8-recorder-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// A display with a single pixel set at (x, 0)
+fn dot(x: usize) -> Display {
+    let mut display = Display::default();
+    display.draw_row(x, 0, 1 << 127, 1);
+    display
+}
+
+// The frames of a GIF with their delays, each pixel being the index into the palette
+fn decode_gif(data: &[u8]) -> Vec<(Vec<u8>, u16)> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(data).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((frame.buffer.to_vec(), frame.delay));
+    }
+    frames
+}
+
+#[test]
+fn frame_paths() {
+    let path = |path, frame| frame_path(path, frame).to_string_lossy().into_owned();
+    assert_eq!(path("out.png", 42), "out-000042.png");
+    assert_eq!(path("out", 0), "out-000000");
+    assert_eq!(path("out.d/frames", 7), "out.d/frames-000007");
+    assert_eq!(path("out.d/frames.pbm", 7), "out.d/frames-000007.pbm");
+    assert_eq!(path("./a.b/c.tar.png", 1), "./a.b/c.tar-000001.png");
+}
+
+#[test]
+fn centiseconds_dont_drift() {
+    assert_eq!(centiseconds(0), 0);
+    assert_eq!(centiseconds(3), 5);
+    assert_eq!(centiseconds(60), 100);
+    // The delays of frames changing every frame add up to the time that has passed
+    let delays: usize = (0..600)
+        .map(|frame| centiseconds(frame + 1) - centiseconds(frame))
+        .sum();
+    assert_eq!(delays, 1000);
+}
+
+#[test]
+fn gif_frames_and_delays() {
+    let mut out = Vec::new();
+    let mut recorder = GifRecorder::new(&mut out, 1, &Palette::default()).unwrap();
+    // Shown for 3, 1 and 6 frames
+    for display in [dot(0), dot(0), dot(0), dot(5)].iter() {
+        recorder.capture(display).unwrap();
+    }
+    for _ in 0..6 {
+        recorder.capture(&dot(0)).unwrap();
+    }
+    recorder.finish().unwrap();
+    drop(recorder);
+
+    let frames = decode_gif(&out);
+    let delays: Vec<u16> = frames.iter().map(|(_, delay)| *delay).collect();
+    assert_eq!(delays, vec![5, 2, 10]);
+    assert_eq!(delays.iter().sum::<u16>() as usize, centiseconds(10));
+
+    // Low resolution frames are scaled up to the size of the high resolution
+    let (image, _) = &frames[1];
+    assert_eq!(image.len(), HIRES_WIDTH * HIRES_HEIGHT);
+    let set: Vec<usize> = (0..image.len()).filter(|&i| image[i] != 0).collect();
+    assert_eq!(set, vec![10, 11, HIRES_WIDTH + 10, HIRES_WIDTH + 11]);
+    assert_eq!(frames[0].0, frames[2].0);
+}
+
+#[test]
+fn identical_frames_are_skipped() {
+    let dir = scratch_dir("sequence");
+    let path = dir.join("frame.pbm");
+    let mut recorder = ImageSequence::new(path.to_str().unwrap(), 1, &Palette::default());
+    for display in [dot(0), dot(0), dot(3), dot(3), dot(0)].iter() {
+        recorder.capture(display).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    let mut files: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec!["frame-000000.pbm", "frame-000002.pbm", "frame-000004.pbm"]
+    );
+
+    let mut expected = Vec::new();
+    dot(3).write_pbm(&mut expected, 1).unwrap();
+    assert_eq!(fs::read(dir.join("frame-000002.pbm")).unwrap(), expected);
+    fs::remove_dir_all(&dir).unwrap();
+}
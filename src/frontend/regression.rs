@@ -0,0 +1,362 @@
+use crate::internals::display::{Display, SpriteEdges};
+use crate::internals::processor::Processor;
+use crate::internals::replay::InputReplay;
+use crate::internals::timing::Timing;
+use crate::internals::variants::Variant;
+use std::any::Any;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How many frames a test runs when the manifest doesn't say
+pub const DEFAULT_FRAMES: usize = 60;
+
+// What the screen has to look like after the last frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expectation {
+    // A PBM screenshot like the ones written by emul8 --screenshot-on-exit, at any scale
+    Pbm(PathBuf),
+    // The Display::checksum of the screen
+    Hash(u64),
+    // Nothing to compare with yet, the test fails and reports the hash it got
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub rom: PathBuf,
+    pub variant: Variant,
+    pub sprite_edges: SpriteEdges,
+    pub timing: Timing,
+    pub frames: usize,
+    // Keypad input recorded by emul8 --record-input, which also provides the random seed
+    pub input: Option<PathBuf>,
+    pub expectation: Expectation,
+}
+
+impl TestCase {
+    pub fn new(rom: PathBuf) -> TestCase {
+        TestCase {
+            name: rom.display().to_string(),
+            rom,
+            variant: Variant::Chip8,
+            sprite_edges: SpriteEdges::Clip,
+            timing: Timing::Fixed,
+            frames: DEFAULT_FRAMES,
+            input: None,
+            expectation: Expectation::Unknown,
+        }
+    }
+}
+
+// A manifest lists one test per line: The ROM followed by options, e.g.
+//   roms/maze.ch8 frames=120 expect=maze.pbm
+//   roms/car.ch8 variant=schip input=car.c8in hash=1F0A3C5E7B9D2468 name=car
+// The options are name, variant, sprite-edges, timing, frames, input, expect and hash. Paths are
+// relative to the directory of the manifest, everything after a # is a comment.
+pub fn parse_manifest(contents: &str, base: &Path) -> Result<Vec<TestCase>, String> {
+    let mut tests = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let rom = match fields.next() {
+            Some(rom) => rom,
+            None => continue,
+        };
+
+        let invalid = |message: String| format!("Line {}: {}", number + 1, message);
+        let mut test = TestCase::new(base.join(rom));
+        test.name = rom.to_string();
+        for field in fields {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("Expected key=value instead of {}", field)))?;
+            match key {
+                "name" => test.name = value.to_string(),
+                "variant" => test.variant = value.parse().map_err(invalid)?,
+                "sprite-edges" => test.sprite_edges = value.parse().map_err(invalid)?,
+                "timing" => test.timing = value.parse().map_err(invalid)?,
+                "frames" => {
+                    test.frames = value
+                        .parse()
+                        .map_err(|_| invalid(format!("Invalid number of frames {}", value)))?
+                }
+                "input" => test.input = Some(base.join(value)),
+                "expect" => test.expectation = Expectation::Pbm(base.join(value)),
+                "hash" => {
+                    test.expectation = Expectation::Hash(
+                        u64::from_str_radix(value, 16)
+                            .map_err(|_| invalid(format!("Invalid hash {}", value)))?,
+                    )
+                }
+                _ => return Err(invalid(format!("Unknown option {}", key))),
+            }
+        }
+
+        tests.push(test);
+    }
+
+    Ok(tests)
+}
+
+pub fn read_manifest(path: &Path) -> Result<Vec<TestCase>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_manifest(&contents, base).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+pub struct Outcome {
+    pub name: String,
+    pub duration: Duration,
+    // Why the test failed, None if it passed
+    pub failure: Option<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+// Runs a single test, a panicking emulator only fails that test
+pub fn run(test: &TestCase) -> Outcome {
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run_test(test)))
+        .unwrap_or_else(|panic| Err(format!("The emulator panicked: {}", panic_message(&*panic))));
+
+    Outcome {
+        name: test.name.clone(),
+        duration: start.elapsed(),
+        failure: result.err(),
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+fn run_test(test: &TestCase) -> Result<(), String> {
+    let mut processor = Processor::new(test.variant.instruction_set());
+    processor.display.sprite_edges = test.sprite_edges;
+    processor.timing = test.timing;
+    processor.keyboard.line_input = false;
+    processor
+        .load_rom(&test.rom.to_string_lossy(), 0x200)
+        .map_err(|error| format!("Unable to load {}: {}", test.rom.display(), error))?;
+    processor.memory.registers.pc = 0x200;
+
+    // Without recorded input the random numbers still have to be the same on every run
+    let mut input = match &test.input {
+        Some(path) => {
+            let file = File::open(path)
+                .map_err(|error| format!("Unable to open {}: {}", path.display(), error))?;
+            let replay = InputReplay::read(BufReader::new(file))
+                .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+            processor.rng = replay.random.source(replay.seed);
+            processor.reseed(replay.seed);
+            Some(replay)
+        }
+        None => {
+            processor.reseed(0);
+            None
+        }
+    };
+
+    // Frame by frame like emul8, so recorded input arrives at the same cycles
+    for _ in 0..test.frames {
+        if processor.halted {
+            break;
+        }
+
+        if let Some(input) = &mut input {
+            input.apply(processor.cycles, &mut processor.keyboard);
+        }
+        processor.run_frame().map_err(|error| {
+            format!(
+                "The program failed at {:#X}: {}",
+                processor.memory.registers.pc, error
+            )
+        })?;
+        processor.tick_timers();
+    }
+
+    let display = &processor.display;
+    match &test.expectation {
+        Expectation::Hash(hash) if display.checksum() == *hash => Ok(()),
+        Expectation::Hash(hash) => Err(format!(
+            "The screen hash is {:016X} instead of {:016X}",
+            display.checksum(),
+            hash
+        )),
+        Expectation::Pbm(path) => compare_pbm(display, path),
+        Expectation::Unknown => Err(format!(
+            "Nothing to compare the screen with, its hash is {:016X}",
+            display.checksum()
+        )),
+    }
+}
+
+// Compares the screen with a PBM screenshot. On a mismatch the actual screen is written next to
+// the expected one as <name>.actual.pbm.
+fn compare_pbm(display: &Display, path: &Path) -> Result<(), String> {
+    let expected =
+        fs::read(path).map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+    let (width, height, expected_bits) =
+        parse_pbm(&expected).map_err(|error| format!("{}: {}", path.display(), error))?;
+
+    let scale = width / display.width();
+    if scale == 0 || width != display.width() * scale || height != display.height() * scale {
+        return Err(format!(
+            "The screen is {}x{}, which can't be scaled to the {}x{} of {}",
+            display.width(),
+            display.height(),
+            width,
+            height,
+            path.display()
+        ));
+    }
+
+    let mut actual = Vec::new();
+    display
+        .write_pbm(&mut actual, scale)
+        .expect("Writing into memory cannot fail");
+    let (_, _, actual_bits) = parse_pbm(&actual).expect("write_pbm writes a valid PBM");
+    if actual_bits == expected_bits {
+        return Ok(());
+    }
+
+    let actual_path = path.with_extension("actual.pbm");
+    fs::write(&actual_path, &actual)
+        .map_err(|error| format!("Unable to write {}: {}", actual_path.display(), error))?;
+    Err(format!(
+        "The screen differs from {}, it has been saved as {}",
+        path.display(),
+        actual_path.display()
+    ))
+}
+
+// The width, height and packed rows of a binary (P4) portable bitmap
+pub fn parse_pbm(data: &[u8]) -> Result<(usize, usize, &[u8]), String> {
+    // The header consists of the magic number, width and height separated by whitespace, with
+    // comments running from # to the end of the line, and a single whitespace before the data
+    let mut position = 0;
+    let mut fields = Vec::with_capacity(3);
+    while fields.len() < 3 {
+        while position < data.len()
+            && (data[position].is_ascii_whitespace() || data[position] == b'#')
+        {
+            if data[position] == b'#' {
+                while position < data.len() && data[position] != b'\n' {
+                    position += 1;
+                }
+            } else {
+                position += 1;
+            }
+        }
+
+        let start = position;
+        while position < data.len() && !data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err("The PBM header is incomplete".to_string());
+        }
+        fields.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+    }
+
+    if fields[0] != "P4" {
+        return Err("Only binary PBM (P4) images are supported".to_string());
+    }
+    let dimension = |field: &str| {
+        field
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid PBM dimension {}", field))
+    };
+    let (width, height) = (dimension(&fields[1])?, dimension(&fields[2])?);
+
+    let bits = data.get(position + 1..).unwrap_or_default();
+    if bits.len() != width.div_ceil(8) * height {
+        return Err("The PBM has the wrong amount of pixel data".to_string());
+    }
+    Ok((width, height, bits))
+}
+
+// Test Anything Protocol, version 13
+pub fn write_tap<W: Write>(out: &mut W, outcomes: &[Outcome]) -> io::Result<()> {
+    writeln!(out, "TAP version 13")?;
+    writeln!(out, "1..{}", outcomes.len())?;
+    for (number, outcome) in outcomes.iter().enumerate() {
+        match &outcome.failure {
+            None => writeln!(out, "ok {} - {}", number + 1, outcome.name)?,
+            Some(failure) => {
+                writeln!(out, "not ok {} - {}", number + 1, outcome.name)?;
+                for line in failure.lines() {
+                    writeln!(out, "# {}", line)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The JUnit XML format understood by most CI servers
+pub fn write_junit<W: Write>(out: &mut W, suite: &str, outcomes: &[Outcome]) -> io::Result<()> {
+    let failures = outcomes.iter().filter(|outcome| !outcome.passed()).count();
+    let total: Duration = outcomes.iter().map(|outcome| outcome.duration).sum();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuite name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+        xml_escape(suite),
+        outcomes.len(),
+        failures,
+        total.as_secs_f64()
+    )?;
+    for outcome in outcomes {
+        let testcase = format!(
+            r#"  <testcase name="{}" classname="{}" time="{:.3}""#,
+            xml_escape(&outcome.name),
+            xml_escape(suite),
+            outcome.duration.as_secs_f64()
+        );
+        match &outcome.failure {
+            None => writeln!(out, "{}/>", testcase)?,
+            Some(failure) => {
+                writeln!(out, "{}>", testcase)?;
+                writeln!(out, r#"    <failure message="{}"/>"#, xml_escape(failure))?;
+                writeln!(out, "  </testcase>")?;
+            }
+        }
+    }
+    writeln!(out, "</testsuite>")
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,228 @@
+use super::*;
+use crate::internals::test_support::program_bytes;
+use std::path::Path;
+
+// A directory of its own for every test, as they run in parallel
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("emul8-regression-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_rom(dir: &Path, words: &[u16]) -> PathBuf {
+    let path = dir.join("test.ch8");
+    fs::write(&path, program_bytes(words)).unwrap();
+    path
+}
+
+// Draws the digit 5 at (5, 5) and waits forever
+const DIGIT_ROM: [u16; 4] = [0x6005, 0xF029, 0xD005, 0x1206];
+
+// The glyph of 5 drawn at (5, 5), without involving the emulator
+fn digit_display() -> Display {
+    let mut display = Display::default();
+    for (y, row) in [0xF0u128, 0x80, 0xF0, 0x10, 0xF0].iter().enumerate() {
+        display.draw_row(5, 5 + y, row << 120, 1);
+    }
+    display
+}
+
+fn failure(test: &TestCase) -> String {
+    run(test).failure.expect("The test should fail")
+}
+
+#[test]
+fn manifest() {
+    let manifest = "# The regression suite\n\
+                    \n\
+                    roms/maze.ch8 frames=120 expect=maze.pbm # seen on the VIP\n\
+                    car.ch8 variant=schip sprite-edges=wrap timing=vip input=car.c8in hash=00000000DEADBEEF name=car\n";
+    let tests = parse_manifest(manifest, Path::new("suite")).unwrap();
+
+    assert_eq!(
+        tests,
+        vec![
+            TestCase {
+                name: "roms/maze.ch8".to_string(),
+                rom: PathBuf::from("suite/roms/maze.ch8"),
+                variant: Variant::Chip8,
+                sprite_edges: SpriteEdges::Clip,
+                timing: Timing::Fixed,
+                frames: 120,
+                input: None,
+                expectation: Expectation::Pbm(PathBuf::from("suite/maze.pbm")),
+            },
+            TestCase {
+                name: "car".to_string(),
+                rom: PathBuf::from("suite/car.ch8"),
+                variant: Variant::SuperChip,
+                sprite_edges: SpriteEdges::Wrap,
+                timing: Timing::Vip,
+                frames: DEFAULT_FRAMES,
+                input: Some(PathBuf::from("suite/car.c8in")),
+                expectation: Expectation::Hash(0xDEAD_BEEF),
+            },
+        ]
+    );
+}
+
+#[test]
+fn invalid_manifests() {
+    let base = Path::new("");
+    assert_eq!(
+        parse_manifest("a.ch8\nb.ch8 frames", base),
+        Err("Line 2: Expected key=value instead of frames".to_string())
+    );
+    assert_eq!(
+        parse_manifest("a.ch8 speed=2", base),
+        Err("Line 1: Unknown option speed".to_string())
+    );
+    assert_eq!(
+        parse_manifest("a.ch8 variant=chip9", base),
+        Err("Line 1: Unknown variant chip9".to_string())
+    );
+    assert_eq!(
+        parse_manifest("a.ch8 hash=xyz", base),
+        Err("Line 1: Invalid hash xyz".to_string())
+    );
+}
+
+#[test]
+fn pbm() {
+    let mut image = Vec::new();
+    digit_display().write_pbm(&mut image, 2).unwrap();
+    let (width, height, bits) = parse_pbm(&image).unwrap();
+    assert_eq!((width, height, bits.len()), (128, 64, 16 * 64));
+
+    let commented = b"P4 # made by hand\n# 2x2 pixels\n2\n2\n\x80\x40";
+    assert_eq!(parse_pbm(commented), Ok((2, 2, &[0x80, 0x40][..])));
+    assert!(parse_pbm(b"P1\n2 2\n1 0 0 1").is_err());
+    assert!(parse_pbm(b"P4\n2 2\n\x80").is_err());
+    assert!(parse_pbm(b"P4\n2").is_err());
+}
+
+#[test]
+fn hash_expectation() {
+    let dir = scratch_dir("hash");
+    let mut test = TestCase::new(write_rom(&dir, &DIGIT_ROM));
+    let hash = digit_display().checksum();
+    assert_eq!(
+        failure(&test),
+        format!(
+            "Nothing to compare the screen with, its hash is {:016X}",
+            hash
+        )
+    );
+
+    test.expectation = Expectation::Hash(hash);
+    assert!(run(&test).passed());
+
+    test.expectation = Expectation::Hash(0);
+    assert_eq!(
+        failure(&test),
+        format!(
+            "The screen hash is {:016X} instead of 0000000000000000",
+            hash
+        )
+    );
+}
+
+#[test]
+fn pbm_expectation() {
+    let dir = scratch_dir("pbm");
+    let expected = dir.join("digit.pbm");
+    let mut image = Vec::new();
+    digit_display().write_pbm(&mut image, 4).unwrap();
+    fs::write(&expected, image).unwrap();
+
+    let mut test = TestCase::new(write_rom(&dir, &DIGIT_ROM));
+    test.expectation = Expectation::Pbm(expected.clone());
+    assert!(run(&test).passed());
+
+    // Nothing has been drawn before the first frame
+    test.frames = 0;
+    assert!(failure(&test).starts_with("The screen differs from"));
+    let actual = fs::read(dir.join("digit.actual.pbm")).unwrap();
+    let (_, _, bits) = parse_pbm(&actual).unwrap();
+    assert!(bits.iter().all(|byte| *byte == 0));
+
+    let mut odd_size = b"P4\n100 50\n".to_vec();
+    odd_size.resize(odd_size.len() + 13 * 50, 0);
+    fs::write(&expected, odd_size).unwrap();
+    assert!(failure(&test).starts_with("The screen is 64x32, which can't be scaled to the 100x50"));
+}
+
+#[test]
+fn recorded_input() {
+    let dir = scratch_dir("input");
+    // Waits for a key and draws its digit
+    let rom = write_rom(&dir, &[0xF00A, 0xF029, 0x6105, 0xD115, 0x1208]);
+    let input = dir.join("keys.c8in");
+    fs::write(&input, "c8in 1\nseed 7\n20 5 down\n40 5 up\nend 100 0\n").unwrap();
+
+    let mut test = TestCase::new(rom);
+    test.frames = 10;
+    test.input = Some(input);
+    test.expectation = Expectation::Hash(digit_display().checksum());
+    assert!(run(&test).passed());
+
+    test.input = None;
+    assert!(failure(&test).starts_with("The screen hash is"));
+}
+
+#[test]
+fn program_errors_and_missing_files() {
+    let dir = scratch_dir("error");
+    let test = TestCase::new(write_rom(&dir, &[0xFFFF]));
+    assert!(failure(&test).starts_with("The program failed at 0x200: Invalid Opcode: FFFF"));
+
+    let test = TestCase::new(dir.join("missing.ch8"));
+    assert!(failure(&test).starts_with("Unable to load"));
+}
+
+fn outcomes() -> Vec<Outcome> {
+    vec![
+        Outcome {
+            name: "maze".to_string(),
+            duration: Duration::from_millis(12),
+            failure: None,
+        },
+        Outcome {
+            name: "<car>".to_string(),
+            duration: Duration::from_millis(250),
+            failure: Some("The screen differs\nfrom \"car.pbm\" & more".to_string()),
+        },
+    ]
+}
+
+#[test]
+fn tap() {
+    let mut out = Vec::new();
+    write_tap(&mut out, &outcomes()).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "TAP version 13\n\
+         1..2\n\
+         ok 1 - maze\n\
+         not ok 2 - <car>\n\
+         # The screen differs\n\
+         # from \"car.pbm\" & more\n"
+    );
+}
+
+#[test]
+fn junit() {
+    let mut out = Vec::new();
+    write_junit(&mut out, "roms", &outcomes()).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuite name=\"roms\" tests=\"2\" failures=\"1\" time=\"0.262\">\n  \
+         <testcase name=\"maze\" classname=\"roms\" time=\"0.012\"/>\n  \
+         <testcase name=\"&lt;car&gt;\" classname=\"roms\" time=\"0.250\">\n    \
+         <failure message=\"The screen differs&#10;from &quot;car.pbm&quot; &amp; more\"/>\n  \
+         </testcase>\n\
+         </testsuite>\n"
+    );
+}
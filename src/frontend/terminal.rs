@@ -0,0 +1,155 @@
+use crate::internals::display::Display;
+use crate::internals::keyboard::Keyboard;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Colors, Print, ResetColor, SetColors};
+use crossterm::terminal::ClearType;
+use crossterm::{cursor, execute, queue, terminal};
+use std::convert::TryFrom;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+// Terminals usually only report key presses (and their auto repeat), so a key counts as held
+// down for this long after it has last been reported
+const KEY_HOLD: Duration = Duration::from_millis(150);
+
+// The keys of the hex keypad, in the order 0 to F, on the left side of a QWERTY keyboard:
+// 1 2 3 C      1 2 3 4
+// 4 5 6 D  ->  Q W E R
+// 7 8 9 E      A S D F
+// A 0 B F      Z X C V
+const KEYMAP: [char; 16] = [
+    'x', '1', '2', '3', 'q', 'w', 'e', 'a', 's', 'd', 'z', 'c', '4', 'r', 'f', 'v',
+];
+
+// Renders the display with half block characters (two pixel rows per character cell) and feeds
+// the pressed keys into the keyboard. The terminal stays in raw mode until this is dropped.
+pub struct Terminal {
+    out: Stdout,
+    colors: Colors,
+    pressed: [Option<Instant>; 16],
+    // The resolution the screen has last been drawn in, everything is redrawn when it changes
+    resolution: (usize, usize),
+}
+
+impl Terminal {
+    pub fn new(foreground: Color, background: Color) -> io::Result<Terminal> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(Terminal {
+            out,
+            colors: Colors::new(foreground, background),
+            pressed: [None; 16],
+            resolution: (0, 0),
+        })
+    }
+
+    // Only redraws the lines containing pixel rows that changed since the last call
+    pub fn render(&mut self, display: &mut Display) -> io::Result<()> {
+        let (width, height) = (display.width(), display.height());
+        let mut dirty_rows = display.take_dirty_rows();
+        if self.resolution == (width, height) && dirty_rows == 0 {
+            return Ok(());
+        }
+
+        queue!(self.out, SetColors(self.colors))?;
+        if self.resolution != (width, height) {
+            self.resolution = (width, height);
+            dirty_rows = !0;
+            queue!(self.out, terminal::Clear(ClearType::All))?;
+        }
+
+        for line in 0..height / 2 {
+            if dirty_rows & (0b11 << (line * 2)) == 0 {
+                continue;
+            }
+
+            let text: String = (0..width)
+                .map(|x| {
+                    let top = display.pixel(x, line * 2) != 0;
+                    let bottom = display.pixel(x, line * 2 + 1) != 0;
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect();
+            queue!(self.out, cursor::MoveTo(0, line as u16), Print(text))?;
+        }
+
+        queue!(self.out, ResetColor)?;
+        self.out.flush()
+    }
+
+    // Handles the pending key events without blocking. Returns false when the user wants to quit
+    // (Escape, or Ctrl+C, which raw mode doesn't turn into a signal anymore)
+    pub fn poll_keys(&mut self, keyboard: &mut Keyboard) -> io::Result<bool> {
+        while event::poll(Duration::from_secs(0))? {
+            if let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind,
+                ..
+            }) = event::read()?
+            {
+                match code {
+                    KeyCode::Esc => return Ok(false),
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(false)
+                    }
+                    KeyCode::Char(c) => {
+                        let c = c.to_ascii_lowercase();
+                        if let Some(key) = KEYMAP.iter().position(|&k| k == c) {
+                            self.pressed[key] = match kind {
+                                KeyEventKind::Release => None,
+                                _ => Some(Instant::now()),
+                            };
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        for (key, pressed) in self.pressed.iter_mut().enumerate() {
+            if pressed.is_some_and(|at| at.elapsed() > KEY_HOLD) {
+                *pressed = None;
+            }
+            keyboard.keys[key] = pressed.is_some();
+        }
+
+        Ok(true)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Nothing sensible left to do when restoring the terminal fails
+        let _ = execute!(
+            self.out,
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Parses either a colour name ("green", "dark_grey", ...) or a hex value like "#33FF66"
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    if let Some(hex) = s.strip_prefix('#') {
+        return match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => Ok(Color::Rgb {
+                r: (rgb >> 16) as u8,
+                g: (rgb >> 8) as u8,
+                b: rgb as u8,
+            }),
+            _ => Err(format!("Invalid colour {}", s)),
+        };
+    }
+
+    Color::try_from(s).map_err(|_| format!("Unknown colour {}", s))
+}
@@ -0,0 +1,57 @@
+use crate::internals::audio::{AudioSink, SAMPLE_RATE};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+// Writes the audio into a 16 bit mono WAV file
+pub struct WavSink {
+    // None once the file has been finished
+    writer: Option<WavWriter<BufWriter<File>>>,
+}
+
+impl WavSink {
+    pub fn create(path: &str) -> io::Result<WavSink> {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+
+        let writer = WavWriter::create(path, spec).map_err(to_io_error)?;
+        Ok(WavSink {
+            writer: Some(writer),
+        })
+    }
+}
+
+fn to_io_error(error: hound::Error) -> io::Error {
+    match error {
+        hound::Error::IoError(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+impl AudioSink for WavSink {
+    fn write_samples(&mut self, samples: &[i16]) -> io::Result<()> {
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("Cannot write to a finished WAV file");
+        for sample in samples {
+            writer.write_sample(*sample).map_err(to_io_error)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.writer.take() {
+            Some(writer) => writer.finalize().map_err(to_io_error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,47 @@
+use super::*;
+use crate::internals::audio::{Beeper, SAMPLES_PER_FRAME};
+use std::fs;
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+#[test]
+fn beeper_into_wav() {
+    let path = std::env::temp_dir().join(format!("emul8-wav-{}.wav", std::process::id()));
+    let mut beeper = Beeper::new(Box::new(WavSink::create(path.to_str().unwrap()).unwrap()));
+    beeper.render_frame(0).unwrap();
+    beeper.render_frame(5).unwrap();
+    beeper.finish().unwrap();
+    let wav = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // The header of a 16 bit mono PCM file
+    let data_len = 2 * SAMPLES_PER_FRAME as u32 * 2;
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(&wav, 4), 36 + data_len);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u16_at(&wav, 20), 1);
+    assert_eq!(u16_at(&wav, 22), 1);
+    assert_eq!(u32_at(&wav, 24), SAMPLE_RATE);
+    assert_eq!(u32_at(&wav, 28), SAMPLE_RATE * 2);
+    assert_eq!(u16_at(&wav, 34), 16);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(&wav, 40), data_len);
+    assert_eq!(wav.len(), 44 + data_len as usize);
+
+    // A silent frame, then the square wave
+    let samples: Vec<i16> = wav[44..]
+        .chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect();
+    assert!(samples[..SAMPLES_PER_FRAME]
+        .iter()
+        .all(|&sample| sample == 0));
+    assert_eq!(samples[SAMPLES_PER_FRAME], beeper.volume);
+    assert_eq!(samples[SAMPLES_PER_FRAME + 50], -beeper.volume);
+}
@@ -0,0 +1,420 @@
+use crate::internals::instruction::Instruction;
+use pest::Parser;
+use std::collections::HashMap;
+
+#[derive(Parser)]
+#[grammar = "grammar/asm.pest"]
+pub struct ASMParser;
+
+pub struct LabelDefinition {
+    pub name: String,
+    pub addr: u16,
+}
+
+// A single line of assembly. Jumps and calls to labels can only be turned into instructions once
+// all labels are known, which happens in the linking stage.
+pub enum Statement {
+    Instruction(Instruction),
+    JmpLabel(String),
+    CallLabel(String),
+}
+
+impl Statement {
+    fn size(&self) -> u16 {
+        match self {
+            Statement::Instruction(instruction) => instruction.size(),
+            _ => 2,
+        }
+    }
+}
+
+// The parsed, but not yet linked source. Label addresses are relative to the start of the program.
+pub struct Program {
+    pub statements: Vec<Statement>,
+    pub label_definitions: HashMap<String, LabelDefinition>,
+}
+
+fn parse_register(pair: pest::iterators::Pair<Rule>) -> Result<u8, String> {
+    let register = pair.as_span().as_str();
+    if pair.as_rule() != Rule::register {
+        return Err(format!(
+            "Syntax Error: Expected a register instead of {}",
+            register
+        ));
+    }
+
+    // The grammar only allows V followed by a single hex digit
+    Ok(u8::from_str_radix(&register[1..], 16).expect("Invalid register in the grammar"))
+}
+
+// Numbers are hex with a 0x prefix, decimal otherwise
+fn parse_number(pair: &pest::iterators::Pair<Rule>) -> Result<u16, String> {
+    let number = pair.as_span().as_str();
+    match number.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => number.parse::<u16>(),
+    }
+    .map_err(|_| format!("Syntax Error: Invalid number {}", number))
+}
+
+fn parse_constant(pair: pest::iterators::Pair<Rule>) -> Result<u8, String> {
+    let constant = parse_number(&pair)?;
+    if constant > 0xFF {
+        return Err(format!(
+            "Syntax Error: Constant {} too large!",
+            pair.as_span().as_str()
+        ));
+    }
+
+    Ok(constant as u8)
+}
+
+// A constant which only has four bits in the instruction, like the height of a sprite
+fn parse_nibble(pair: pest::iterators::Pair<Rule>) -> Result<u8, String> {
+    let nibble = parse_number(&pair)?;
+    if nibble > 0xF {
+        return Err(format!(
+            "Syntax Error: Constant {} too large, it has to fit into four bits",
+            pair.as_span().as_str()
+        ));
+    }
+
+    Ok(nibble as u8)
+}
+
+fn parse_address(pair: pest::iterators::Pair<Rule>) -> Result<u16, String> {
+    let addr = parse_number(&pair)?;
+    if addr > 0xFFF {
+        return Err(format!(
+            "Syntax Error: Address out of boundaries: {}",
+            pair.as_span().as_str()
+        ));
+    }
+
+    Ok(addr)
+}
+
+fn parse_long_address(pair: pest::iterators::Pair<Rule>) -> Result<u16, String> {
+    parse_number(&pair).map_err(|_| {
+        format!(
+            "Syntax Error: Address out of boundaries: {}",
+            pair.as_span().as_str()
+        )
+    })
+}
+
+// Parses the source of a whole file
+pub fn parse(contents: &str) -> Result<Program, String> {
+    // into_inner to not have file as Rule but all the expressions
+    let parse_file = ASMParser::parse(Rule::file, contents)
+        .map_err(|e| e.to_string())?
+        .next()
+        .expect("The grammar always produces a file")
+        .into_inner();
+
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut label_definitions = HashMap::new();
+    // The address of the next statement, relative to the start of the program
+    let mut addr: u16 = 0;
+
+    for pair in parse_file {
+        //Rule::COMMENT => println!("Comment: {}", pair.as_span().as_str()),
+        if pair.as_rule() == Rule::opcode {
+            let statement: Option<Statement> = match pair.as_span().as_str() {
+                "CLS" => Some(Statement::Instruction(Instruction::CLS)),
+                "RET" => Some(Statement::Instruction(Instruction::RET)),
+                "SCR" => Some(Statement::Instruction(Instruction::SCR)),
+                "SCL" => Some(Statement::Instruction(Instruction::SCL)),
+                "EXIT" => Some(Statement::Instruction(Instruction::EXIT)),
+                "LOW" => Some(Statement::Instruction(Instruction::LOW)),
+                "HIGH" => Some(Statement::Instruction(Instruction::HIGH)),
+                "AUDIO" => Some(Statement::Instruction(Instruction::AUDIO)),
+                _ => {
+                    let opcode_str = pair.as_span().as_str();
+                    let opcode_node = &mut pair.into_inner();
+                    // The grammar makes sure every operator has all of its operands
+                    let mut operand =
+                        || opcode_node.next().expect("Missing operand in the grammar");
+                    let operator = operand();
+                    let unsupported =
+                        || format!("Syntax Error: Unsupported operands: {}", opcode_str);
+                    match operator.as_rule() {
+                        Rule::ld_operator => {
+                            let operand1 = operand();
+                            let operand2 = operand();
+                            Some(Statement::Instruction(match operand1.as_rule() {
+                                Rule::register => match operand2.as_rule() {
+                                    Rule::special_register => match operand2.as_span().as_str() {
+                                        "K" => Instruction::LDVxK {
+                                            reg: parse_register(operand1)?,
+                                        },
+                                        "DT" => Instruction::LDVxDT {
+                                            reg: parse_register(operand1)?,
+                                        },
+                                        "I" => Instruction::LDVxI {
+                                            reg: parse_register(operand1)?,
+                                        },
+                                        "R" => Instruction::LDVxR {
+                                            reg: parse_register(operand1)?,
+                                        },
+                                        _ => return Err(unsupported()),
+                                    },
+                                    Rule::register => Instruction::LDVxVy {
+                                        reg_a: parse_register(operand1)?,
+                                        reg_b: parse_register(operand2)?,
+                                    },
+                                    Rule::address => Instruction::LDVxByte {
+                                        reg: parse_register(operand1)?,
+                                        byte: parse_constant(operand2)?,
+                                    },
+                                    _ => unreachable!(),
+                                },
+                                Rule::special_register => {
+                                    let register = parse_register(operand2)?;
+                                    match operand1.as_span().as_str() {
+                                        "B" => Instruction::LDBVx { reg: register },
+                                        "F" => Instruction::LDFVx { reg: register },
+                                        "I" => Instruction::LDIVx { reg: register },
+                                        "DT" => Instruction::LDDTVx { reg: register },
+                                        "ST" => Instruction::LDSTVx { reg: register },
+                                        "HF" => Instruction::LDHFVx { reg: register },
+                                        "R" => Instruction::LDRVx { reg: register },
+                                        _ => return Err(unsupported()),
+                                    }
+                                }
+                                _ => unreachable!(),
+                            }))
+                        }
+                        Rule::call_operator => {
+                            let operand = operand();
+                            Some(match operand.as_rule() {
+                                Rule::address => Statement::Instruction(Instruction::CALL {
+                                    address: parse_address(operand)?,
+                                }),
+                                Rule::identifier => {
+                                    Statement::CallLabel(operand.as_span().as_str().to_string())
+                                }
+                                _ => unreachable!("Unknown CALL Operand {:?}", operand.as_rule()),
+                            })
+                        }
+                        Rule::jmp_operator => {
+                            let operand = operand();
+                            Some(match operand.as_rule() {
+                                Rule::address => Statement::Instruction(Instruction::JMP {
+                                    address: parse_address(operand)?,
+                                }),
+                                Rule::identifier => {
+                                    Statement::JmpLabel(operand.as_span().as_str().to_string())
+                                }
+                                _ => unreachable!("Unknown JMP Operand {:?}", operand.as_rule()),
+                            })
+                        }
+                        Rule::jp_operator => {
+                            let register = parse_register(operand())?;
+                            if register != 0 {
+                                return Err(format!(
+                                    "Syntax Error: JP only jumps relative to V0: {}",
+                                    opcode_str
+                                ));
+                            }
+
+                            Some(Statement::Instruction(Instruction::JPV0Offset {
+                                address: parse_address(operand())?,
+                            }))
+                        }
+                        Rule::rnd_operator => {
+                            Some(Statement::Instruction(Instruction::RNDVxByte {
+                                reg: parse_register(operand())?,
+                                byte: parse_constant(operand())?,
+                            }))
+                        }
+                        Rule::key_operator => {
+                            let reg = parse_register(operand())?;
+                            Some(Statement::Instruction(match operator.as_span().as_str() {
+                                "SKP" => Instruction::SKPKBRDVx { reg },
+                                "SKNP" => Instruction::SKNPBRDVx { reg },
+                                _ => unreachable!(),
+                            }))
+                        }
+                        Rule::conditionals => {
+                            let register = parse_register(operand())?;
+                            let op2 = operand();
+
+                            Some(Statement::Instruction(match operator.as_span().as_str() {
+                                "SE" => match op2.as_rule() {
+                                    Rule::register => Instruction::SEVxVy {
+                                        reg_a: register,
+                                        reg_b: parse_register(op2)?,
+                                    },
+                                    Rule::address => Instruction::SEVxByte {
+                                        reg: register,
+                                        byte: parse_constant(op2)?,
+                                    },
+                                    _ => unreachable!(),
+                                },
+                                "SNE" => match op2.as_rule() {
+                                    Rule::register => Instruction::SNEVxVy {
+                                        reg_a: register,
+                                        reg_b: parse_register(op2)?,
+                                    },
+                                    Rule::address => Instruction::SNEVxByte {
+                                        reg: register,
+                                        byte: parse_constant(op2)?,
+                                    },
+                                    _ => unreachable!(),
+                                },
+                                _ => unreachable!(),
+                            }))
+                        }
+                        Rule::math_operator => {
+                            let op1 = operand();
+                            let op2 = operand();
+
+                            Some(Statement::Instruction(match operator.as_span().as_str() {
+                                "ADD" => match op1.as_rule() {
+                                    Rule::special_register if op1.as_span().as_str() == "I" => {
+                                        Instruction::ADDIVx {
+                                            reg: parse_register(op2)?,
+                                        }
+                                    }
+                                    Rule::register => match op2.as_rule() {
+                                        Rule::register => Instruction::ADDVxVy {
+                                            reg_a: parse_register(op1)?,
+                                            reg_b: parse_register(op2)?,
+                                        },
+                                        Rule::address => Instruction::ADDVxByte {
+                                            reg: parse_register(op1)?,
+                                            byte: parse_constant(op2)?,
+                                        },
+                                        _ => unreachable!(),
+                                    },
+                                    _ => return Err(unsupported()),
+                                },
+                                other => {
+                                    let reg_a = parse_register(op1)?;
+                                    let reg_b = parse_register(op2)?;
+                                    match other {
+                                        "SUB" => Instruction::SUBVxVy { reg_a, reg_b },
+                                        "SUBN" => Instruction::SUBNVxVy { reg_a, reg_b },
+                                        "OR" => Instruction::ORVxVy { reg_a, reg_b },
+                                        "AND" => Instruction::ANDVxVy { reg_a, reg_b },
+                                        "XOR" => Instruction::XORVxVy { reg_a, reg_b },
+                                        "SHR" => Instruction::SHRVxVy { reg_a, reg_b },
+                                        "SHL" => Instruction::SHLVxVy { reg_a, reg_b },
+                                        _ => unreachable!(),
+                                    }
+                                }
+                            }))
+                        }
+                        Rule::ldi_operator => {
+                            let op = operand();
+
+                            Some(Statement::Instruction(match op.as_rule() {
+                                Rule::register => Instruction::LDIVx {
+                                    reg: parse_register(op)?,
+                                },
+                                Rule::address => Instruction::LDIAddr {
+                                    address: parse_address(op)?,
+                                },
+                                _ => unreachable!(),
+                            }))
+                        }
+                        Rule::scroll_operator => Some(Statement::Instruction(Instruction::SCD {
+                            rows: parse_nibble(operand())?,
+                        })),
+                        Rule::ldil_operator => Some(Statement::Instruction(Instruction::LDILong {
+                            address: parse_long_address(operand())?,
+                        })),
+                        Rule::range_operator => {
+                            let reg_a = parse_register(operand())?;
+                            let reg_b = parse_register(operand())?;
+
+                            Some(Statement::Instruction(match operator.as_span().as_str() {
+                                "SAVE" => Instruction::SAVEVxVy { reg_a, reg_b },
+                                "LOAD" => Instruction::LOADVxVy { reg_a, reg_b },
+                                _ => unreachable!(),
+                            }))
+                        }
+                        Rule::plane_operator => Some(Statement::Instruction(Instruction::PLANE {
+                            planes: parse_nibble(operand())?,
+                        })),
+                        Rule::pitch_operator => {
+                            Some(Statement::Instruction(Instruction::PITCHVx {
+                                reg: parse_register(operand())?,
+                            }))
+                        }
+                        Rule::drw_operator => Some(Statement::Instruction(Instruction::DRW {
+                            reg_x: parse_register(operand())?,
+                            reg_y: parse_register(operand())?,
+                            size: parse_nibble(operand())?,
+                        })),
+                        // Byte arithmetic is only allowed here because we know the string is no Unicode.
+                        Rule::label_definition => {
+                            let s = &opcode_str[..opcode_str.len() - 1];
+                            label_definitions.insert(
+                                s.to_string(),
+                                LabelDefinition {
+                                    name: s.to_string(),
+                                    addr,
+                                },
+                            );
+                            None
+                        }
+                        _ => unreachable!("Unknown OPCODE {}", opcode_str),
+                    }
+                }
+            };
+
+            // If statement is Some, push it to statements
+            if let Some(statement) = statement {
+                addr = addr
+                    .checked_add(statement.size())
+                    .ok_or_else(|| "The program doesn't fit into the memory".to_string())?;
+                statements.push(statement)
+            };
+        }
+    }
+
+    Ok(Program {
+        statements,
+        label_definitions,
+    })
+}
+
+impl Program {
+    // Resolves the labels, given the address the program will be loaded at
+    pub fn link(&self, offset: u16) -> Result<Vec<Instruction>, String> {
+        let resolve = |name: &String| match self.label_definitions.get(name) {
+            Some(label) => match label.addr.checked_add(offset) {
+                Some(addr) if addr <= 0xFFF => Ok(addr),
+                _ => Err(format!("ERROR LNK002: Label {} is out of reach", name)),
+            },
+            None => Err(format!("ERROR LNK001: Unresolved Label {}", name)),
+        };
+
+        self.statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Instruction(instruction) => Ok(*instruction),
+                Statement::JmpLabel(name) => Ok(Instruction::JMP {
+                    address: resolve(name)?,
+                }),
+                Statement::CallLabel(name) => Ok(Instruction::CALL {
+                    address: resolve(name)?,
+                }),
+            })
+            .collect()
+    }
+}
+
+// Parses and links the source in one go, returning the binary
+pub fn assemble(contents: &str, offset: u16) -> Result<Vec<u8>, String> {
+    Ok(parse(contents)?
+        .link(offset)?
+        .iter()
+        .flat_map(|instruction| instruction.encode_bytes())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
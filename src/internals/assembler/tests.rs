@@ -0,0 +1,130 @@
+use super::*;
+use crate::internals::disassembler::Linear;
+use crate::internals::opcode::{InvalidOpcode, Opcode};
+use crate::internals::processor::Processor;
+use crate::internals::variants::Variant;
+use proptest::prelude::*;
+
+fn variant() -> impl Strategy<Value = Variant> {
+    prop_oneof![
+        Just(Variant::Chip8),
+        Just(Variant::SuperChip),
+        Just(Variant::XoChip)
+    ]
+}
+
+fn decode(variant: Variant, word: u16) -> Box<dyn Opcode> {
+    let [high, low] = word.to_be_bytes();
+    variant.instruction_set().decode((high, low))
+}
+
+// Every single word instruction of the dialect it belongs to
+fn valid_word() -> impl Strategy<Value = (Variant, u16)> {
+    (variant(), any::<u16>()).prop_filter("invalid opcode", |&(variant, word)| {
+        !decode(variant, word).is::<InvalidOpcode>()
+    })
+}
+
+proptest! {
+    #[test]
+    fn decode_assemble_round_trip((variant, word) in valid_word()) {
+        let op = decode(variant, word);
+        prop_assert_eq!(u16::from_be_bytes([op.assemble().0, op.assemble().1]), word);
+        prop_assert_eq!(op.instruction(), Instruction::decode(word));
+        prop_assert_eq!(Instruction::decode(word).encode(), word);
+    }
+
+    #[test]
+    fn asm_disasm_round_trip((variant, word) in valid_word()) {
+        let op = decode(variant, word);
+        prop_assert_eq!(assemble(&op.to_string(), 0x200).unwrap(), word.to_be_bytes().to_vec());
+        // The canonical output of disasm, with the address as comment
+        let canonical = format!("{} ; {:#X}", op, 0x200);
+        prop_assert_eq!(assemble(&canonical, 0x200).unwrap(), word.to_be_bytes().to_vec());
+    }
+
+    #[test]
+    fn asm_disasm_round_trip_long(address in any::<u16>()) {
+        let op = Instruction::LDILong { address }.opcode();
+        let [high, low] = address.to_be_bytes();
+        prop_assert_eq!(assemble(&op.to_string(), 0x200).unwrap(), vec![0xF0, 0x00, high, low]);
+    }
+
+    #[test]
+    fn disassembling_random_bytes_never_panics(
+        variant in variant(),
+        bytes in prop::collection::vec(any::<u8>(), 1..256),
+        position in any::<prop::sample::Index>(),
+    ) {
+        let mut processor = Processor::new(variant.instruction_set());
+        // Anywhere in memory, especially at its very end
+        let loading_point = position.index(processor.memory.ram.len() - bytes.len() + 1);
+        processor.memory.load(&bytes, loading_point as u16);
+
+        for (_, _, op) in Linear::new(&mut processor, loading_point as u16).take(bytes.len()) {
+            let _ = op.to_string();
+            let _ = op.instruction();
+        }
+    }
+}
+
+#[test]
+fn labels() {
+    let source = "start:\nCLS\nloop:\nCALL sub\nJMP loop\nsub:\nRET\nJMP start\n";
+    assert_eq!(
+        assemble(source, 0x200).unwrap(),
+        vec![0x00, 0xE0, 0x22, 0x06, 0x12, 0x02, 0x00, 0xEE, 0x12, 0x00]
+    );
+}
+
+#[test]
+fn disassembling_long_instruction_at_the_end() {
+    let mut processor = Processor::new(Variant::XoChip.instruction_set());
+    processor.memory.load(&[0xF0, 0x00], 0xFFFE);
+    let ops: Vec<_> = Linear::new(&mut processor, 0xFFFE).collect();
+    assert_eq!(ops.len(), 1);
+}
+
+// Sources found by the fuzz target in fuzz/, which used to panic
+#[test]
+fn errors() {
+    let error = |source| assemble(source, 0x200).unwrap_err();
+    assert_eq!(error("JMP ABC"), "Syntax Error: Invalid number ABC");
+    assert_eq!(
+        error("LD V0, 0x100"),
+        "Syntax Error: Constant 0x100 too large!"
+    );
+    assert_eq!(
+        error("DRW V0, V1, 0x10"),
+        "Syntax Error: Constant 0x10 too large, it has to fit into four bits"
+    );
+    assert_eq!(
+        error("LD K, V0"),
+        "Syntax Error: Unsupported operands: LD K, V0"
+    );
+    assert_eq!(
+        error("LD V0, ST"),
+        "Syntax Error: Unsupported operands: LD V0, ST"
+    );
+    assert_eq!(
+        error("ADD DT, V0"),
+        "Syntax Error: Unsupported operands: ADD DT, V0"
+    );
+    assert_eq!(
+        error("SUB V0, 5"),
+        "Syntax Error: Expected a register instead of 5"
+    );
+    assert_eq!(
+        error("JP V1, 0x200"),
+        "Syntax Error: JP only jumps relative to V0: JP V1, 0x200"
+    );
+    assert_eq!(
+        error("JMP nowhere"),
+        "ERROR LNK001: Unresolved Label nowhere"
+    );
+    assert_eq!(
+        assemble("JMP out\nout:", 0xFFF).unwrap_err(),
+        "ERROR LNK002: Label out is out of reach"
+    );
+    assert!(error("CLS CLS").contains("expected EOI"));
+}
@@ -0,0 +1,66 @@
+use std::io;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+// The timers run at 60 Hz, so the audio is produced in chunks of one frame
+pub const SAMPLES_PER_FRAME: usize = SAMPLE_RATE as usize / 60;
+
+// Where the produced audio ends up: 16 bit mono samples at SAMPLE_RATE
+pub trait AudioSink {
+    fn write_samples(&mut self, samples: &[i16]) -> io::Result<()>;
+
+    // Has to be called after the last samples have been written
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+// The single tone buzzer of the COSMAC VIP: A square wave that sounds as long as the sound timer
+// is active
+pub struct Beeper {
+    pub frequency: u32,
+    pub volume: i16,
+    sink: Box<dyn AudioSink>,
+    // The position within the current period of the wave, in samples, so the wave continues
+    // seamlessly across frames
+    phase: u32,
+    samples: Vec<i16>,
+}
+
+impl Beeper {
+    pub fn new(sink: Box<dyn AudioSink>) -> Beeper {
+        Beeper {
+            frequency: 440,
+            volume: i16::MAX / 4,
+            sink,
+            phase: 0,
+            samples: Vec::with_capacity(SAMPLES_PER_FRAME),
+        }
+    }
+
+    // Produces the samples for one frame, has to be called before the timers are counted down
+    pub fn render_frame(&mut self, st: u8) -> io::Result<()> {
+        let period = SAMPLE_RATE / self.frequency;
+        self.samples.clear();
+
+        for _ in 0..SAMPLES_PER_FRAME {
+            let sample = if st == 0 {
+                0
+            } else if self.phase < period / 2 {
+                self.volume
+            } else {
+                -self.volume
+            };
+
+            self.samples.push(sample);
+            self.phase = (self.phase + 1) % period;
+        }
+
+        self.sink.write_samples(&self.samples)
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.sink.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,56 @@
+use super::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Keeps the samples, so the test can look at them
+struct Samples(Rc<RefCell<Vec<i16>>>);
+
+impl AudioSink for Samples {
+    fn write_samples(&mut self, samples: &[i16]) -> io::Result<()> {
+        self.0.borrow_mut().extend_from_slice(samples);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn beeper() -> (Beeper, Rc<RefCell<Vec<i16>>>) {
+    let samples = Rc::new(RefCell::new(Vec::new()));
+    (Beeper::new(Box::new(Samples(samples.clone()))), samples)
+}
+
+#[test]
+fn one_frame_per_tick() {
+    let (mut beeper, samples) = beeper();
+    beeper.render_frame(0).unwrap();
+    assert_eq!(samples.borrow().len(), 735);
+    beeper.render_frame(3).unwrap();
+    assert_eq!(samples.borrow().len(), 2 * SAMPLES_PER_FRAME);
+}
+
+#[test]
+fn silent_without_sound_timer() {
+    let (mut beeper, samples) = beeper();
+    beeper.render_frame(0).unwrap();
+    assert!(samples.borrow().iter().all(|&sample| sample == 0));
+}
+
+#[test]
+fn square_wave_with_sound_timer() {
+    let (mut beeper, samples) = beeper();
+    beeper.render_frame(1).unwrap();
+    beeper.render_frame(1).unwrap();
+    let samples = samples.borrow();
+
+    // 440 Hz are a period of 100 samples, half of them high and half of them low
+    let high = beeper.volume;
+    assert!(samples[..50].iter().all(|&sample| sample == high));
+    assert!(samples[50..100].iter().all(|&sample| sample == -high));
+    // The wave continues across frames
+    for (i, sample) in samples.iter().enumerate() {
+        let expected = if i % 100 < 50 { high } else { -high };
+        assert_eq!(*sample, expected, "sample {}", i);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::internals::opcode::Opcode;
+use crate::internals::processor::Processor;
+
+// A linear disassembler: decodes one instruction after another, from the start address up to the
+// end of the memory, without following any jumps. Yields the address, the first word and the
+// decoded instruction, which is InvalidOpcode for data.
+pub struct Linear<'a> {
+    processor: &'a mut Processor,
+    pc: Option<u16>,
+}
+
+impl<'a> Linear<'a> {
+    pub fn new(processor: &'a mut Processor, start: u16) -> Linear<'a> {
+        Linear {
+            processor,
+            pc: Some(start),
+        }
+    }
+}
+
+impl Iterator for Linear<'_> {
+    type Item = (u16, (u8, u8), Box<dyn Opcode>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pc = self.pc?;
+        let last_word = (self.processor.memory.ram.len() - 2) as u16;
+        if pc > last_word {
+            return None;
+        }
+
+        let opcode = self.processor.memory.read_two_u8(pc);
+        let op = self.processor.decode_at(pc);
+        // XO-CHIP has 64 KiB of memory, so the pc can't be advanced past the last word
+        self.pc = pc.checked_add(op.size());
+        Some((pc, opcode, op))
+    }
+}
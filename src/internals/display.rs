@@ -1,26 +1,339 @@
+use crate::internals::savestate;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+// The classic CHIP-8 resolution
+pub const LORES_WIDTH: usize = 64;
+pub const LORES_HEIGHT: usize = 32;
+
+// The SUPER-CHIP high resolution mode (enabled by HIGH, disabled by LOW)
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+
+// What happens to the parts of a sprite that are drawn past the edges of the screen. The position
+// a sprite starts at always wraps around, only the original interpreter clips the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpriteEdges {
+    Clip,
+    Wrap,
+}
+
+impl FromStr for SpriteEdges {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clip" => Ok(SpriteEdges::Clip),
+            "wrap" => Ok(SpriteEdges::Wrap),
+            _ => Err(format!("Unknown sprite edge mode {}", s)),
+        }
+    }
+}
+
+// XO-CHIP has two bitplanes, all other dialects only use the first one
+pub const PLANES: usize = 2;
+
+// The colours of the pixels in screenshots, indexed by the bitmask of the planes a pixel is set in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [[u8; 3]; 4],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            colors: [
+                [0x00, 0x00, 0x00],
+                [0xFF, 0xFF, 0xFF],
+                [0xAA, 0xAA, 0xAA],
+                [0x55, 0x55, 0x55],
+            ],
+        }
+    }
+}
+
+// A comma separated list of up to four hex colours like "#000000,#33FF66", the missing ones
+// keep their default
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Palette::default();
+        let colors: Vec<&str> = s.split(',').map(|color| color.trim()).collect();
+        if colors.len() > palette.colors.len() {
+            return Err(format!("Too many colours in palette {}", s));
+        }
+
+        for (i, color) in colors.into_iter().enumerate() {
+            let hex = color.trim_start_matches('#');
+            palette.colors[i] = match u32::from_str_radix(hex, 16) {
+                Ok(rgb) if hex.len() == 6 => [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8],
+                _ => return Err(format!("Invalid colour {}", color)),
+            };
+        }
+
+        Ok(palette)
+    }
+}
+
 pub struct Display {
-    pub screen: [bool; 64 * 32],
+    // Every row of every bitplane packed into a single integer, the leftmost pixel being the
+    // highest bit. Always large enough for the high resolution, in lores mode only the first 64
+    // bits of the first 32 rows are used.
+    pub bitplanes: [[u128; HIRES_HEIGHT]; PLANES],
+    pub hires: bool,
+    // The bitplanes affected by drawing, clearing and scrolling (XO-CHIP's Fn01), defaults to the first
+    pub planes: u8,
+    pub sprite_edges: SpriteEdges,
+    // One bit per row that has changed since the last call to take_dirty_rows
+    dirty_rows: u64,
 }
 
 impl Default for Display {
     fn default() -> Self {
         Display {
-            screen: [false; 64 * 32],
+            bitplanes: [[0; HIRES_HEIGHT]; PLANES],
+            hires: false,
+            planes: 1,
+            sprite_edges: SpriteEdges::Clip,
+            dirty_rows: 0,
+        }
+    }
+}
+
+impl Display {
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            LORES_WIDTH
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            LORES_HEIGHT
+        }
+    }
+
+    // The bits of a row that are part of the screen in the current resolution
+    fn row_mask(&self) -> u128 {
+        !0u128 << (HIRES_WIDTH - self.width())
+    }
+
+    // The indices of the bitplanes selected by planes
+    fn selected_planes(&self) -> impl Iterator<Item = usize> {
+        let planes = self.planes;
+        (0..PLANES).filter(move |plane| planes & (1 << plane) != 0)
+    }
+
+    // The bitmask of the planes the pixel at (x, y) is set in
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let bit = 1u128 << (HIRES_WIDTH - 1 - x);
+        (0..PLANES)
+            .filter(|&plane| self.bitplanes[plane][y] & bit != 0)
+            .fold(0, |mask, plane| mask | 1 << plane)
+    }
+
+    // Every pixel that is set in any plane as (x, y, planes), row by row
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        (0..self.height()).flat_map(move |y| {
+            let mut row = self.bitplanes.iter().fold(0, |row, plane| row | plane[y]);
+            std::iter::from_fn(move || {
+                if row == 0 {
+                    return None;
+                }
+
+                let x = row.leading_zeros() as usize;
+                row &= !(1u128 << (HIRES_WIDTH - 1 - x));
+                Some((x, y, self.pixel(x, y)))
+            })
+        })
+    }
+
+    // Whether anything has been drawn since the last call to take_dirty_rows
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_rows != 0
+    }
+
+    // Returns one bit per row (row 0 being the lowest bit) that has changed since the last call,
+    // so frontends only need to redraw those
+    pub fn take_dirty_rows(&mut self) -> u64 {
+        std::mem::replace(&mut self.dirty_rows, 0)
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty_rows = !0;
+    }
+
+    // Only clears the selected planes
+    pub fn clear(&mut self) {
+        for plane in self.selected_planes() {
+            self.bitplanes[plane] = [0; HIRES_HEIGHT];
+        }
+        self.mark_all_dirty();
+    }
+
+    // Switching the resolution clears all planes, as the old contents have a different layout
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.bitplanes = [[0; HIRES_HEIGHT]; PLANES];
+        self.mark_all_dirty();
+    }
+
+    // XORs a row of a sprite into the given plane (a bitmask, like planes) at (x, y). The sprite
+    // row is aligned like the rows of the screen, its first pixel being the highest bit. x has to
+    // be on the screen, while y may lie below it, see SpriteEdges for what happens to the parts
+    // past the edges. Returns whether any pixel has been unset by this (a collision).
+    pub fn draw_row(&mut self, x: usize, y: usize, sprite: u128, plane: u8) -> bool {
+        let (width, height) = (self.width(), self.height());
+        let (row, y) = match self.sprite_edges {
+            SpriteEdges::Clip if y >= height => return false,
+            SpriteEdges::Clip => (sprite >> x, y),
+            // Whatever has been shifted past the right edge is moved back to the left one
+            SpriteEdges::Wrap => (
+                (sprite >> x) | sprite.checked_shl((width - x) as u32).unwrap_or(0),
+                y % height,
+            ),
+        };
+        let row = row & self.row_mask();
+
+        let plane = &mut self.bitplanes[plane.trailing_zeros() as usize][y];
+        let collision = *plane & row != 0;
+        *plane ^= row;
+        if row != 0 {
+            self.dirty_rows |= 1 << y;
+        }
+        collision
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        let height = self.height();
+        for plane in self.selected_planes() {
+            let bitplane = &mut self.bitplanes[plane];
+            for y in (0..height).rev() {
+                bitplane[y] = if y >= rows { bitplane[y - rows] } else { 0 };
+            }
         }
+        self.mark_all_dirty();
+    }
+
+    pub fn scroll_right(&mut self, columns: usize) {
+        let (height, mask) = (self.height(), self.row_mask());
+        for plane in self.selected_planes() {
+            for row in &mut self.bitplanes[plane][..height] {
+                *row = (*row >> columns) & mask;
+            }
+        }
+        self.mark_all_dirty();
+    }
+
+    pub fn scroll_left(&mut self, columns: usize) {
+        let height = self.height();
+        for plane in self.selected_planes() {
+            for row in &mut self.bitplanes[plane][..height] {
+                *row <<= columns;
+            }
+        }
+        self.mark_all_dirty();
+    }
+}
+
+// A hash of everything on the screen, to compare it against a known good one without keeping
+// a screenshot around
+impl Display {
+    pub fn checksum(&self) -> u64 {
+        let mut bytes = vec![self.hires as u8];
+        for plane in self.bitplanes.iter() {
+            for row in plane.iter() {
+                bytes.extend_from_slice(&row.to_be_bytes());
+            }
+        }
+        savestate::hash(&bytes)
+    }
+}
+
+// Screenshots, every pixel is scaled up to a square of scale * scale pixels
+impl Display {
+    // Calls write_row with every (already scaled) row of the image, each pixel being the bitmask
+    // of the planes it is set in
+    fn scaled_rows<F>(&self, scale: usize, mut write_row: F) -> io::Result<()>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        let mut row = Vec::with_capacity(self.width() * scale);
+        for y in 0..self.height() {
+            row.clear();
+            for x in 0..self.width() {
+                let pixel = self.pixel(x, y);
+                row.extend(std::iter::repeat_n(pixel, scale));
+            }
+
+            for _ in 0..scale {
+                write_row(&row)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // A monochrome (binary) portable bitmap, where a pixel is black when it is set in any plane
+    pub fn write_pbm<W: Write>(&self, out: &mut W, scale: usize) -> io::Result<()> {
+        let (width, height) = (self.width() * scale, self.height() * scale);
+        write!(out, "P4\n{} {}\n", width, height)?;
+
+        let mut packed = vec![0u8; width.div_ceil(8)];
+        self.scaled_rows(scale, |row| {
+            packed.iter_mut().for_each(|byte| *byte = 0);
+            for (x, pixel) in row.iter().enumerate() {
+                if *pixel != 0 {
+                    packed[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            out.write_all(&packed)
+        })
+    }
+
+    // The whole (scaled) image, row by row, to be used with the palette
+    pub fn image(&self, scale: usize) -> Vec<u8> {
+        let mut image = Vec::with_capacity(self.width() * self.height() * scale * scale);
+        let result: io::Result<()> = self.scaled_rows(scale, |row| {
+            image.extend_from_slice(row);
+            Ok(())
+        });
+        result.expect("Collecting the rows cannot fail");
+        image
+    }
+
+    pub fn write_png<W: Write>(&self, out: W, scale: usize, palette: &Palette) -> io::Result<()> {
+        let (width, height) = (self.width() * scale, self.height() * scale);
+        let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette.colors.concat());
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.image(scale))?;
+        writer.finish()?;
+        Ok(())
     }
 }
 
 impl std::fmt::Display for Display {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..32 {
-            for x in 0..64 {
-                if self.screen[64 * y + x] {
-                    write!(f, "*")?;
-                } else {
-                    write!(f, " ")?;
+        let (width, height) = (self.width(), self.height());
+        for y in 0..height {
+            for x in 0..width {
+                match self.pixel(x, y) {
+                    0 => write!(f, " ")?,
+                    1 => write!(f, "*")?,
+                    2 => write!(f, "+")?,
+                    _ => write!(f, "#")?,
                 }
 
-                if x != 63 {
+                if x != width - 1 {
                     write!(f, " ")?;
                 } else {
                     writeln!(f)?;
@@ -31,3 +344,6 @@ impl std::fmt::Display for Display {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests;
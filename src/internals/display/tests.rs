@@ -0,0 +1,138 @@
+use super::*;
+use crate::internals::font::FONT_ADDR;
+use crate::internals::processor::Processor;
+use crate::internals::variants::Variant;
+
+// A row of a sprite made of the byte, starting at the left edge
+fn byte(byte: u8) -> u128 {
+    (byte as u128) << 120
+}
+
+fn display(sprite_edges: SpriteEdges) -> Display {
+    Display {
+        sprite_edges,
+        ..Display::default()
+    }
+}
+
+fn pixels(display: &Display) -> Vec<(usize, usize, u8)> {
+    display.pixels().collect()
+}
+
+#[test]
+fn dirty_rows() {
+    let mut d = Display::default();
+    assert!(!d.is_dirty());
+    d.draw_row(8, 3, byte(0xF0), 1);
+    d.draw_row(8, 5, byte(0xF0), 1);
+    assert!(d.is_dirty());
+    assert_eq!(d.take_dirty_rows(), 1 << 3 | 1 << 5);
+    assert_eq!(d.take_dirty_rows(), 0);
+
+    // Drawing nothing, or only past the edge, changes nothing
+    d.draw_row(8, 7, 0, 1);
+    d.draw_row(8, LORES_HEIGHT, byte(0xF0), 1);
+    assert!(!d.is_dirty());
+
+    d.clear();
+    assert_eq!(d.take_dirty_rows(), !0);
+    d.scroll_down(2);
+    assert_eq!(d.take_dirty_rows(), !0);
+    d.scroll_left(4);
+    assert_eq!(d.take_dirty_rows(), !0);
+    d.set_hires(true);
+    assert_eq!(d.take_dirty_rows(), !0);
+    d.mark_all_dirty();
+    assert_eq!(d.take_dirty_rows(), !0);
+}
+
+#[test]
+fn dirty_rows_of_instructions() {
+    // DRW V0, V1, 5 with the glyph of 0 at (0, 3), then CLS and SCD 1
+    let mut p = Processor::with_program(Variant::SuperChip, &[0xD015, 0x00E0, 0x00C1]);
+    p.memory.registers.i = FONT_ADDR;
+    p.memory.registers.v[1] = 3;
+
+    p.tick().unwrap();
+    assert_eq!(p.display.take_dirty_rows(), 0b11111 << 3);
+    p.tick().unwrap();
+    assert_eq!(p.display.take_dirty_rows(), !0);
+    p.tick().unwrap();
+    assert_eq!(p.display.take_dirty_rows(), !0);
+}
+
+#[test]
+fn pixels_in_lores() {
+    let mut d = Display::default();
+    d.draw_row(62, 1, byte(0b1100_0000), 1);
+    d.draw_row(63, 1, byte(0b1000_0000), 2);
+    d.draw_row(0, 0, byte(0b1000_0000), 2);
+    assert_eq!(pixels(&d), vec![(0, 0, 2), (62, 1, 1), (63, 1, 3)]);
+
+    // Unset pixels are left out
+    d.draw_row(62, 1, byte(0b1000_0000), 1);
+    assert_eq!(pixels(&d), vec![(0, 0, 2), (63, 1, 3)]);
+}
+
+#[test]
+fn pixels_in_hires() {
+    let mut d = Display::default();
+    d.set_hires(true);
+    d.draw_row(64, 40, byte(0b1000_0000), 1);
+    d.draw_row(127, 63, byte(0b1000_0000), 1);
+    assert_eq!(pixels(&d), vec![(64, 40, 1), (127, 63, 1)]);
+    assert_eq!(d.pixel(127, 63), 1);
+
+    // Switching back clears the screen
+    d.set_hires(false);
+    assert!(pixels(&d).is_empty());
+}
+
+#[test]
+fn clip_at_the_edges() {
+    let mut d = display(SpriteEdges::Clip);
+    assert!(!d.draw_row(60, 0, byte(0xFF), 1));
+    assert_eq!(
+        d.pixels().map(|(x, ..)| x).collect::<Vec<_>>(),
+        vec![60, 61, 62, 63]
+    );
+    // Clipped pixels don't collide
+    assert!(d.draw_row(62, 0, byte(0xFF), 1));
+    assert_eq!(
+        d.pixels().map(|(x, ..)| x).collect::<Vec<_>>(),
+        vec![60, 61]
+    );
+
+    let mut d = display(SpriteEdges::Clip);
+    assert!(!d.draw_row(0, LORES_HEIGHT, byte(0xFF), 1));
+    assert!(pixels(&d).is_empty());
+
+    let mut d = display(SpriteEdges::Clip);
+    d.set_hires(true);
+    d.draw_row(124, HIRES_HEIGHT - 1, byte(0xFF), 1);
+    d.draw_row(0, HIRES_HEIGHT, byte(0xFF), 1);
+    let expected: Vec<_> = (124..128).map(|x| (x, HIRES_HEIGHT - 1, 1)).collect();
+    assert_eq!(pixels(&d), expected);
+}
+
+#[test]
+fn wrap_at_the_edges() {
+    let mut d = display(SpriteEdges::Wrap);
+    d.draw_row(60, 0, byte(0xFF), 1);
+    assert_eq!(
+        d.pixels().map(|(x, ..)| x).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 60, 61, 62, 63]
+    );
+    // The wrapped pixels collide as well
+    assert!(d.draw_row(0, 0, byte(0x80), 1));
+
+    let mut d = display(SpriteEdges::Wrap);
+    d.draw_row(0, LORES_HEIGHT + 1, byte(0x80), 1);
+    assert_eq!(pixels(&d), vec![(0, 1, 1)]);
+    assert_eq!(d.take_dirty_rows(), 1 << 1);
+
+    let mut d = display(SpriteEdges::Wrap);
+    d.set_hires(true);
+    d.draw_row(126, HIRES_HEIGHT, byte(0xC0 | 0x08), 1);
+    assert_eq!(pixels(&d), vec![(2, 0, 1), (126, 0, 1), (127, 0, 1)]);
+}
@@ -0,0 +1,50 @@
+use crate::internals::memory::MemoryError;
+use std::fmt;
+
+// Errors of the running program, which stop it at the instruction that caused them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    // An access outside of the memory
+    Memory(MemoryError),
+    // An unknown opcode or one whose operands the machine doesn't support
+    InvalidInstruction { opcode: u16 },
+    // A CALL with all depth entries of the stack in use
+    StackOverflow { depth: usize },
+    // A RET without a CALL
+    StackUnderflow,
+}
+
+impl From<MemoryError> for Fault {
+    fn from(error: MemoryError) -> Self {
+        Fault::Memory(error)
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::Memory(error) => error.fmt(f),
+            Fault::InvalidInstruction { opcode } => {
+                write!(f, "Invalid Opcode: {:04X} cannot be executed", opcode)
+            }
+            Fault::StackOverflow { depth } => write!(
+                f,
+                "Stack Overflow: The stack only holds {} nested subroutine calls",
+                depth
+            ),
+            Fault::StackUnderflow => write!(
+                f,
+                "Stack Underflow: Tried to return without a subroutine call"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Fault {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Fault::Memory(error) => Some(error),
+            _ => None,
+        }
+    }
+}
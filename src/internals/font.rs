@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// Where the hex font is loaded by default, like most emulators do. The big font follows it.
+pub const FONT_ADDR: u16 = 0x50;
+// 16 glyphs, 5 rows each for the hex font and 10 rows for the SUPER-CHIP one
+pub const GLYPH_LEN: usize = 5;
+pub const BIG_GLYPH_LEN: usize = 10;
+pub const FONT_LEN: usize = 16 * GLYPH_LEN;
+pub const BIG_FONT_LEN: usize = 16 * BIG_GLYPH_LEN;
+
+type Glyphs = [[u8; GLYPH_LEN]; 16];
+type BigGlyphs = [[u8; BIG_GLYPH_LEN]; 16];
+
+// The hex digits of the interpreters that came with the different machines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontSet {
+    // The font most emulators use, based on the ones below
+    Standard,
+    Vip,
+    Dream6800,
+    Eti660,
+    // The 3 pixels wide font of SUPER-CHIP 1.1 on the HP-48
+    SuperChip,
+}
+
+impl FontSet {
+    pub fn glyphs(self) -> &'static Glyphs {
+        match self {
+            FontSet::Standard => &STANDARD,
+            FontSet::Vip => &VIP,
+            FontSet::Dream6800 => &DREAM_6800,
+            FontSet::Eti660 => &ETI_660,
+            FontSet::SuperChip => &SUPER_CHIP,
+        }
+    }
+}
+
+impl FromStr for FontSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(FontSet::Standard),
+            "vip" => Ok(FontSet::Vip),
+            "dream6800" => Ok(FontSet::Dream6800),
+            "eti660" => Ok(FontSet::Eti660),
+            "schip" => Ok(FontSet::SuperChip),
+            _ => Err(format!("Unknown font {}", s)),
+        }
+    }
+}
+
+const STANDARD: Glyphs = [
+    [0xF0, 0x90, 0x90, 0x90, 0xF0], // 0
+    [0x20, 0x60, 0x20, 0x20, 0x70], // 1
+    [0xF0, 0x10, 0xF0, 0x80, 0xF0], // 2
+    [0xF0, 0x10, 0xF0, 0x10, 0xF0], // 3
+    [0x90, 0x90, 0xF0, 0x10, 0x10], // 4
+    [0xF0, 0x80, 0xF0, 0x10, 0xF0], // 5
+    [0xF0, 0x80, 0xF0, 0x90, 0xF0], // 6
+    [0xF0, 0x10, 0x20, 0x40, 0x40], // 7
+    [0xF0, 0x90, 0xF0, 0x90, 0xF0], // 8
+    [0xF0, 0x90, 0xF0, 0x10, 0xF0], // 9
+    [0xF0, 0x90, 0xF0, 0x90, 0x90], // A
+    [0xE0, 0x90, 0xE0, 0x90, 0xF0], // B
+    [0xF0, 0x80, 0x80, 0x80, 0xF0], // C
+    [0xE0, 0x90, 0x90, 0x90, 0xE0], // D
+    [0xF0, 0x80, 0xF0, 0x80, 0xF0], // E
+    [0xF0, 0x80, 0xF0, 0x80, 0x80], // F
+];
+
+const VIP: Glyphs = [
+    [0xF0, 0x90, 0x90, 0x90, 0xF0], // 0
+    [0x60, 0x20, 0x20, 0x20, 0x70], // 1
+    [0xF0, 0x10, 0xF0, 0x80, 0xF0], // 2
+    [0xF0, 0x10, 0xF0, 0x10, 0xF0], // 3
+    [0xA0, 0xA0, 0xF0, 0x20, 0x20], // 4
+    [0xF0, 0x80, 0xF0, 0x10, 0xF0], // 5
+    [0xF0, 0x80, 0xF0, 0x90, 0xF0], // 6
+    [0xF0, 0x10, 0x10, 0x10, 0x10], // 7
+    [0xF0, 0x90, 0xF0, 0x90, 0xF0], // 8
+    [0xF0, 0x90, 0xF0, 0x10, 0xF0], // 9
+    [0xF0, 0x90, 0xF0, 0x90, 0x90], // A
+    [0xF0, 0x50, 0x70, 0x50, 0xF0], // B
+    [0xF0, 0x80, 0x80, 0x80, 0xF0], // C
+    [0xF0, 0x50, 0x50, 0x50, 0xF0], // D
+    [0xF0, 0x80, 0xF0, 0x80, 0xF0], // E
+    [0xF0, 0x80, 0xF0, 0x80, 0x80], // F
+];
+
+const DREAM_6800: Glyphs = [
+    [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], // 0
+    [0x40, 0x40, 0x40, 0x40, 0x40], // 1
+    [0xE0, 0x20, 0xE0, 0x80, 0xE0], // 2
+    [0xE0, 0x20, 0xE0, 0x20, 0xE0], // 3
+    [0x80, 0xA0, 0xA0, 0xE0, 0x20], // 4
+    [0xE0, 0x80, 0xE0, 0x20, 0xE0], // 5
+    [0xE0, 0x80, 0xE0, 0xA0, 0xE0], // 6
+    [0xE0, 0x20, 0x20, 0x20, 0x20], // 7
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], // 8
+    [0xE0, 0xA0, 0xE0, 0x20, 0xE0], // 9
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], // A
+    [0xC0, 0xA0, 0xE0, 0xA0, 0xC0], // B
+    [0xE0, 0x80, 0x80, 0x80, 0xE0], // C
+    [0xC0, 0xA0, 0xA0, 0xA0, 0xC0], // D
+    [0xE0, 0x80, 0xE0, 0x80, 0xE0], // E
+    [0xE0, 0x80, 0xC0, 0x80, 0x80], // F
+];
+
+const ETI_660: Glyphs = [
+    [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], // 0
+    [0x20, 0x20, 0x20, 0x20, 0x20], // 1
+    [0xE0, 0x20, 0xE0, 0x80, 0xE0], // 2
+    [0xE0, 0x20, 0xE0, 0x20, 0xE0], // 3
+    [0xA0, 0xA0, 0xE0, 0x20, 0x20], // 4
+    [0xE0, 0x80, 0xE0, 0x20, 0xE0], // 5
+    [0xE0, 0x80, 0xE0, 0xA0, 0xE0], // 6
+    [0xE0, 0x20, 0x20, 0x20, 0x20], // 7
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], // 8
+    [0xE0, 0xA0, 0xE0, 0x20, 0xE0], // 9
+    [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], // A
+    [0x80, 0x80, 0xE0, 0xA0, 0xE0], // B
+    [0xE0, 0x80, 0x80, 0x80, 0xE0], // C
+    [0x20, 0x20, 0xE0, 0xA0, 0xE0], // D
+    [0xE0, 0x80, 0xE0, 0x80, 0xE0], // E
+    [0xE0, 0x80, 0xC0, 0x80, 0x80], // F
+];
+
+const SUPER_CHIP: Glyphs = [
+    [0x60, 0xA0, 0xA0, 0xA0, 0xC0], // 0
+    [0x40, 0xC0, 0x40, 0x40, 0xE0], // 1
+    [0xC0, 0x20, 0x40, 0x80, 0xE0], // 2
+    [0xC0, 0x20, 0x40, 0x20, 0xC0], // 3
+    [0x20, 0xA0, 0xE0, 0x20, 0x20], // 4
+    [0xE0, 0x80, 0xC0, 0x20, 0xC0], // 5
+    [0x40, 0x80, 0xC0, 0xA0, 0x40], // 6
+    [0xE0, 0x20, 0x60, 0x40, 0x40], // 7
+    [0x40, 0xA0, 0x40, 0xA0, 0x40], // 8
+    [0x40, 0xA0, 0x60, 0x20, 0x40], // 9
+    [0x40, 0xA0, 0xE0, 0xA0, 0xA0], // A
+    [0xC0, 0xA0, 0xC0, 0xA0, 0xC0], // B
+    [0x60, 0x80, 0x80, 0x80, 0x60], // C
+    [0xC0, 0xA0, 0xA0, 0xA0, 0xC0], // D
+    [0xE0, 0x80, 0xC0, 0x80, 0xE0], // E
+    [0xE0, 0x80, 0xC0, 0x80, 0x80], // F
+];
+
+// The 10 byte high resolution font of SUPER-CHIP
+const BIG: BigGlyphs = [
+    [0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF], // 0
+    [0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF], // 1
+    [0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF], // 2
+    [0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF], // 3
+    [0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03], // 4
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF], // 5
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF], // 6
+    [0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18], // 7
+    [0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF], // 8
+    [0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF], // 9
+    [0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3], // A
+    [0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC], // B
+    [0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C], // C
+    [0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC], // D
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF], // E
+    [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0], // F
+];
+
+// The glyphs LDFVx and LDHFVx point I to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    pub small: Glyphs,
+    pub big: BigGlyphs,
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::builtin(FontSet::Standard)
+    }
+}
+
+impl Font {
+    pub fn builtin(set: FontSet) -> Font {
+        Font {
+            small: *set.glyphs(),
+            big: BIG,
+        }
+    }
+
+    // A custom font consists of the 80 bytes of the hex font, optionally followed by the 160
+    // bytes of the big font. Without them, the SUPER-CHIP big font is used.
+    pub fn from_bytes(data: &[u8]) -> Result<Font, String> {
+        let mut font = Font::default();
+        match data.len() {
+            FONT_LEN => font.small.as_flattened_mut().copy_from_slice(data),
+            len if len == FONT_LEN + BIG_FONT_LEN => {
+                let (small, big) = data.split_at(FONT_LEN);
+                font.small.as_flattened_mut().copy_from_slice(small);
+                font.big.as_flattened_mut().copy_from_slice(big);
+            }
+            len => {
+                return Err(format!(
+                    "A font consists of {} or {} bytes, not {}",
+                    FONT_LEN,
+                    FONT_LEN + BIG_FONT_LEN,
+                    len
+                ))
+            }
+        }
+        Ok(font)
+    }
+
+    pub fn read(path: &Path) -> Result<Font, String> {
+        let data = fs::read(path)
+            .map_err(|error| format!("Unable to read {}: {}", path.display(), error))?;
+        Font::from_bytes(&data).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+#[cfg(test)]
+mod tests;
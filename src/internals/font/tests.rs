@@ -0,0 +1,74 @@
+use super::*;
+use crate::internals::memory::MemoryError;
+use crate::internals::processor::Processor;
+use crate::internals::variants::Variant;
+
+#[test]
+fn builtin_fonts() {
+    for name in ["standard", "vip", "dream6800", "eti660", "schip"].iter() {
+        let glyphs = name.parse::<FontSet>().unwrap().glyphs();
+        // Every digit looks different
+        for (digit, glyph) in glyphs.iter().enumerate() {
+            assert!(!glyphs[digit + 1..].contains(glyph), "{} {:X}", name, digit);
+        }
+    }
+    assert!("chip48".parse::<FontSet>().is_err());
+}
+
+#[test]
+fn custom_fonts() {
+    let data: Vec<u8> = (0..(FONT_LEN + BIG_FONT_LEN) as u8).collect();
+    let font = Font::from_bytes(&data[..FONT_LEN]).unwrap();
+    assert_eq!(font.small[1], [5, 6, 7, 8, 9]);
+    assert_eq!(font.big, Font::default().big);
+
+    let font = Font::from_bytes(&data).unwrap();
+    assert_eq!(font.big[0][0], FONT_LEN as u8);
+    assert_eq!(
+        Font::from_bytes(&data[..10]),
+        Err("A font consists of 80 or 240 bytes, not 10".to_string())
+    );
+}
+
+#[test]
+fn font_location() {
+    let mut p = Processor::new(Variant::SuperChip.instruction_set());
+    p.set_font(&Font::builtin(FontSet::Vip), 0x100).unwrap();
+    assert!(p.memory.ram[..0x100].iter().all(|&byte| byte == 0));
+    assert_eq!(p.memory.ram[0x100 + 7 * 5..0x100 + 8 * 5], VIP[7]);
+    assert_eq!(p.memory.ram[0x150..0x15A], BIG[0]);
+
+    p.load_program(&[0x6007, 0xF029, 0xF030]);
+    p.tick().unwrap();
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.i, 0x100 + 7 * 5);
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.i, 0x150 + 7 * 10);
+}
+
+#[test]
+fn font_outside_of_the_memory() {
+    let mut p = Processor::new(Variant::Chip8.instruction_set());
+    let font = Font::builtin(FontSet::Vip);
+    let last = (p.memory.ram.len() - FONT_LEN - BIG_FONT_LEN) as u16;
+    assert_eq!(
+        p.set_font(&font, last + 1),
+        Err(MemoryError::OutOfBounds { addr: 0x1000 })
+    );
+    // The font stays where it was
+    assert_eq!(p.memory.font_addr, FONT_ADDR);
+    assert_eq!(p.memory.ram[FONT_ADDR as usize..][..5], STANDARD[0]);
+
+    p.set_font(&font, last).unwrap();
+    assert_eq!(p.memory.ram[last as usize..][..5], VIP[0]);
+
+    // Even at the end of the 64 KiB of XO-CHIP, the glyph addresses don't overflow
+    let mut p = Processor::new(Variant::XoChip.instruction_set());
+    let last = (p.memory.ram.len() - FONT_LEN - BIG_FONT_LEN) as u16;
+    p.set_font(&font, last).unwrap();
+    assert!(p.set_font(&font, 0xFFFF).is_err());
+    p.memory.registers.v[0] = 0xF;
+    p.load_program(&[0xF030]);
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.i, 0xFFFF - 9);
+}
@@ -0,0 +1,678 @@
+use crate::internals::opcode::*;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+// Every opcode of every supported dialect as a plain value, so tools can pattern-match on it
+// instead of downcasting Box<dyn Opcode>. The variants are named like the corresponding Opcode
+// structs, which are still what gets executed (see Instruction::opcode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    InvalidOpcode { opcode: u16 },
+    CLS,
+    RET,
+    JMP { address: u16 },
+    CALL { address: u16 },
+    SEVxByte { reg: u8, byte: u8 },
+    SNEVxByte { reg: u8, byte: u8 },
+    SEVxVy { reg_a: u8, reg_b: u8 },
+    SNEVxVy { reg_a: u8, reg_b: u8 },
+    LDVxByte { reg: u8, byte: u8 },
+    ADDVxByte { reg: u8, byte: u8 },
+    LDVxVy { reg_a: u8, reg_b: u8 },
+    ORVxVy { reg_a: u8, reg_b: u8 },
+    ANDVxVy { reg_a: u8, reg_b: u8 },
+    XORVxVy { reg_a: u8, reg_b: u8 },
+    ADDVxVy { reg_a: u8, reg_b: u8 },
+    SUBVxVy { reg_a: u8, reg_b: u8 },
+    SHRVxVy { reg_a: u8, reg_b: u8 },
+    SUBNVxVy { reg_a: u8, reg_b: u8 },
+    SHLVxVy { reg_a: u8, reg_b: u8 },
+    LDVxDT { reg: u8 },
+    LDVxK { reg: u8 },
+    LDDTVx { reg: u8 },
+    LDSTVx { reg: u8 },
+    ADDIVx { reg: u8 },
+    LDFVx { reg: u8 },
+    LDBVx { reg: u8 },
+    LDIVx { reg: u8 },
+    LDVxI { reg: u8 },
+    SKPKBRDVx { reg: u8 },
+    SKNPBRDVx { reg: u8 },
+    DRW { reg_x: u8, reg_y: u8, size: u8 },
+    RNDVxByte { reg: u8, byte: u8 },
+    LDIAddr { address: u16 },
+    JPV0Offset { address: u16 },
+    // SUPER-CHIP
+    SCD { rows: u8 },
+    SCR,
+    SCL,
+    EXIT,
+    LOW,
+    HIGH,
+    LDHFVx { reg: u8 },
+    LDRVx { reg: u8 },
+    LDVxR { reg: u8 },
+    // XO-CHIP
+    LDILong { address: u16 },
+    SAVEVxVy { reg_a: u8, reg_b: u8 },
+    LOADVxVy { reg_a: u8, reg_b: u8 },
+    PLANE { planes: u8 },
+    AUDIO,
+    PITCHVx { reg: u8 },
+}
+
+impl Instruction {
+    // Decodes a single instruction word, regardless of the dialect it belongs to. XO-CHIP's
+    // F000 NNNN needs the following word as well, so it is only decoded by decode_long.
+    pub fn decode(word: u16) -> Instruction {
+        let sel = (word >> 12) as u8;
+        let x = ((word >> 8) & 0xF) as u8;
+        let y = ((word >> 4) & 0xF) as u8;
+        let n = (word & 0xF) as u8;
+        let byte = (word & 0xFF) as u8;
+        let address = word & 0xFFF;
+
+        match sel {
+            0x0 => match word {
+                0x00E0 => Instruction::CLS,
+                0x00EE => Instruction::RET,
+                0x00FB => Instruction::SCR,
+                0x00FC => Instruction::SCL,
+                0x00FD => Instruction::EXIT,
+                0x00FE => Instruction::LOW,
+                0x00FF => Instruction::HIGH,
+                _ if word & 0xFFF0 == 0x00C0 => Instruction::SCD { rows: n },
+                _ => Instruction::InvalidOpcode { opcode: word },
+            },
+            0x1 => Instruction::JMP { address },
+            0x2 => Instruction::CALL { address },
+            0x3 => Instruction::SEVxByte { reg: x, byte },
+            0x4 => Instruction::SNEVxByte { reg: x, byte },
+            0x5 => match n {
+                0 => Instruction::SEVxVy { reg_a: x, reg_b: y },
+                2 => Instruction::SAVEVxVy { reg_a: x, reg_b: y },
+                3 => Instruction::LOADVxVy { reg_a: x, reg_b: y },
+                _ => Instruction::InvalidOpcode { opcode: word },
+            },
+            0x6 => Instruction::LDVxByte { reg: x, byte },
+            0x7 => Instruction::ADDVxByte { reg: x, byte },
+            0x8 => match n {
+                0 => Instruction::LDVxVy { reg_a: x, reg_b: y },
+                1 => Instruction::ORVxVy { reg_a: x, reg_b: y },
+                2 => Instruction::ANDVxVy { reg_a: x, reg_b: y },
+                3 => Instruction::XORVxVy { reg_a: x, reg_b: y },
+                4 => Instruction::ADDVxVy { reg_a: x, reg_b: y },
+                5 => Instruction::SUBVxVy { reg_a: x, reg_b: y },
+                6 => Instruction::SHRVxVy { reg_a: x, reg_b: y },
+                7 => Instruction::SUBNVxVy { reg_a: x, reg_b: y },
+                0xE => Instruction::SHLVxVy { reg_a: x, reg_b: y },
+                _ => Instruction::InvalidOpcode { opcode: word },
+            },
+            0x9 => match n {
+                0 => Instruction::SNEVxVy { reg_a: x, reg_b: y },
+                _ => Instruction::InvalidOpcode { opcode: word },
+            },
+            0xA => Instruction::LDIAddr { address },
+            0xB => Instruction::JPV0Offset { address },
+            0xC => Instruction::RNDVxByte { reg: x, byte },
+            0xD => Instruction::DRW {
+                reg_x: x,
+                reg_y: y,
+                size: n,
+            },
+            0xE => match byte {
+                0x9E => Instruction::SKPKBRDVx { reg: x },
+                0xA1 => Instruction::SKNPBRDVx { reg: x },
+                _ => Instruction::InvalidOpcode { opcode: word },
+            },
+            _ => match byte {
+                0x01 => Instruction::PLANE { planes: x },
+                0x02 if x == 0 => Instruction::AUDIO,
+                0x07 => Instruction::LDVxDT { reg: x },
+                0x0A => Instruction::LDVxK { reg: x },
+                0x15 => Instruction::LDDTVx { reg: x },
+                0x18 => Instruction::LDSTVx { reg: x },
+                0x1E => Instruction::ADDIVx { reg: x },
+                0x29 => Instruction::LDFVx { reg: x },
+                0x30 => Instruction::LDHFVx { reg: x },
+                0x33 => Instruction::LDBVx { reg: x },
+                0x3A => Instruction::PITCHVx { reg: x },
+                0x55 => Instruction::LDIVx { reg: x },
+                0x65 => Instruction::LDVxI { reg: x },
+                0x75 => Instruction::LDRVx { reg: x },
+                0x85 => Instruction::LDVxR { reg: x },
+                _ => Instruction::InvalidOpcode { opcode: word },
+            },
+        }
+    }
+
+    // Decodes an instruction which may be longer than one word, operand is the word following it
+    pub fn decode_long(word: u16, operand: u16) -> Instruction {
+        match word {
+            0xF000 => Instruction::LDILong { address: operand },
+            _ => Instruction::decode(word),
+        }
+    }
+
+    // The size in bytes, see Opcode::size
+    pub fn size(&self) -> u16 {
+        match self {
+            Instruction::LDILong { .. } => 4,
+            _ => 2,
+        }
+    }
+
+    // The first (and usually only) word of the instruction
+    pub fn encode(&self) -> u16 {
+        match *self {
+            Instruction::InvalidOpcode { opcode } => opcode,
+            Instruction::CLS => 0x00E0,
+            Instruction::RET => 0x00EE,
+            Instruction::JMP { address } => with_address(0x1, address),
+            Instruction::CALL { address } => with_address(0x2, address),
+            Instruction::SEVxByte { reg, byte } => with_byte(0x3, reg, byte),
+            Instruction::SNEVxByte { reg, byte } => with_byte(0x4, reg, byte),
+            Instruction::SEVxVy { reg_a, reg_b } => with_nibbles(0x5, reg_a, reg_b, 0x0),
+            Instruction::SNEVxVy { reg_a, reg_b } => with_nibbles(0x9, reg_a, reg_b, 0x0),
+            Instruction::LDVxByte { reg, byte } => with_byte(0x6, reg, byte),
+            Instruction::ADDVxByte { reg, byte } => with_byte(0x7, reg, byte),
+            Instruction::LDVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x0),
+            Instruction::ORVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x1),
+            Instruction::ANDVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x2),
+            Instruction::XORVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x3),
+            Instruction::ADDVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x4),
+            Instruction::SUBVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x5),
+            Instruction::SHRVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x6),
+            Instruction::SUBNVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0x7),
+            Instruction::SHLVxVy { reg_a, reg_b } => with_nibbles(0x8, reg_a, reg_b, 0xE),
+            Instruction::LDVxDT { reg } => with_byte(0xF, reg, 0x07),
+            Instruction::LDVxK { reg } => with_byte(0xF, reg, 0x0A),
+            Instruction::LDDTVx { reg } => with_byte(0xF, reg, 0x15),
+            Instruction::LDSTVx { reg } => with_byte(0xF, reg, 0x18),
+            Instruction::ADDIVx { reg } => with_byte(0xF, reg, 0x1E),
+            Instruction::LDFVx { reg } => with_byte(0xF, reg, 0x29),
+            Instruction::LDBVx { reg } => with_byte(0xF, reg, 0x33),
+            Instruction::LDIVx { reg } => with_byte(0xF, reg, 0x55),
+            Instruction::LDVxI { reg } => with_byte(0xF, reg, 0x65),
+            Instruction::SKPKBRDVx { reg } => with_byte(0xE, reg, 0x9E),
+            Instruction::SKNPBRDVx { reg } => with_byte(0xE, reg, 0xA1),
+            Instruction::DRW { reg_x, reg_y, size } => with_nibbles(0xD, reg_x, reg_y, size),
+            Instruction::RNDVxByte { reg, byte } => with_byte(0xC, reg, byte),
+            Instruction::LDIAddr { address } => with_address(0xA, address),
+            Instruction::JPV0Offset { address } => with_address(0xB, address),
+            Instruction::SCD { rows } => 0x00C0 | (rows & 0xF) as u16,
+            Instruction::SCR => 0x00FB,
+            Instruction::SCL => 0x00FC,
+            Instruction::EXIT => 0x00FD,
+            Instruction::LOW => 0x00FE,
+            Instruction::HIGH => 0x00FF,
+            Instruction::LDHFVx { reg } => with_byte(0xF, reg, 0x30),
+            Instruction::LDRVx { reg } => with_byte(0xF, reg, 0x75),
+            Instruction::LDVxR { reg } => with_byte(0xF, reg, 0x85),
+            Instruction::LDILong { .. } => 0xF000,
+            Instruction::SAVEVxVy { reg_a, reg_b } => with_nibbles(0x5, reg_a, reg_b, 0x2),
+            Instruction::LOADVxVy { reg_a, reg_b } => with_nibbles(0x5, reg_a, reg_b, 0x3),
+            Instruction::PLANE { planes } => with_byte(0xF, planes, 0x01),
+            Instruction::AUDIO => 0xF002,
+            Instruction::PITCHVx { reg } => with_byte(0xF, reg, 0x3A),
+        }
+    }
+
+    // All bytes of the instruction, including the operand of long instructions
+    pub fn encode_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.encode().to_be_bytes().to_vec();
+        if let Instruction::LDILong { address } = *self {
+            bytes.extend_from_slice(&address.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn opcode(&self) -> Box<dyn Opcode> {
+        match *self {
+            Instruction::InvalidOpcode { opcode } => Box::new(InvalidOpcode {
+                opcode: ((opcode >> 8) as u8, opcode as u8),
+            }),
+            Instruction::CLS => Box::new(CLS {}),
+            Instruction::RET => Box::new(RET {}),
+            Instruction::JMP { address } => Box::new(JMP { address }),
+            Instruction::CALL { address } => Box::new(CALL { address }),
+            Instruction::SEVxByte { reg, byte } => Box::new(SEVxByte { reg, byte }),
+            Instruction::SNEVxByte { reg, byte } => Box::new(SNEVxByte { reg, byte }),
+            Instruction::SEVxVy { reg_a, reg_b } => Box::new(SEVxVy { reg_a, reg_b }),
+            Instruction::SNEVxVy { reg_a, reg_b } => Box::new(SNEVxVy { reg_a, reg_b }),
+            Instruction::LDVxByte { reg, byte } => Box::new(LDVxByte { reg, byte }),
+            Instruction::ADDVxByte { reg, byte } => Box::new(ADDVxByte { reg, byte }),
+            Instruction::LDVxVy { reg_a, reg_b } => Box::new(LDVxVy { reg_a, reg_b }),
+            Instruction::ORVxVy { reg_a, reg_b } => Box::new(ORVxVy { reg_a, reg_b }),
+            Instruction::ANDVxVy { reg_a, reg_b } => Box::new(ANDVxVy { reg_a, reg_b }),
+            Instruction::XORVxVy { reg_a, reg_b } => Box::new(XORVxVy { reg_a, reg_b }),
+            Instruction::ADDVxVy { reg_a, reg_b } => Box::new(ADDVxVy { reg_a, reg_b }),
+            Instruction::SUBVxVy { reg_a, reg_b } => Box::new(SUBVxVy { reg_a, reg_b }),
+            Instruction::SHRVxVy { reg_a, reg_b } => Box::new(SHRVxVy { reg_a, reg_b }),
+            Instruction::SUBNVxVy { reg_a, reg_b } => Box::new(SUBNVxVy { reg_a, reg_b }),
+            Instruction::SHLVxVy { reg_a, reg_b } => Box::new(SHLVxVy { reg_a, reg_b }),
+            Instruction::LDVxDT { reg } => Box::new(LDVxDT { reg }),
+            Instruction::LDVxK { reg } => Box::new(LDVxK { reg }),
+            Instruction::LDDTVx { reg } => Box::new(LDDTVx { reg }),
+            Instruction::LDSTVx { reg } => Box::new(LDSTVx { reg }),
+            Instruction::ADDIVx { reg } => Box::new(ADDIVx { reg }),
+            Instruction::LDFVx { reg } => Box::new(LDFVx { reg }),
+            Instruction::LDBVx { reg } => Box::new(LDBVx { reg }),
+            Instruction::LDIVx { reg } => Box::new(LDIVx { reg }),
+            Instruction::LDVxI { reg } => Box::new(LDVxI { reg }),
+            Instruction::SKPKBRDVx { reg } => Box::new(SKPKBRDVx { reg }),
+            Instruction::SKNPBRDVx { reg } => Box::new(SKNPBRDVx { reg }),
+            Instruction::DRW { reg_x, reg_y, size } => Box::new(DRW { reg_x, reg_y, size }),
+            Instruction::RNDVxByte { reg, byte } => Box::new(RNDVxByte { reg, byte }),
+            Instruction::LDIAddr { address } => Box::new(LDIAddr { address }),
+            Instruction::JPV0Offset { address } => Box::new(JPV0Offset { address }),
+            Instruction::SCD { rows } => Box::new(SCD { rows }),
+            Instruction::SCR => Box::new(SCR {}),
+            Instruction::SCL => Box::new(SCL {}),
+            Instruction::EXIT => Box::new(EXIT {}),
+            Instruction::LOW => Box::new(LOW {}),
+            Instruction::HIGH => Box::new(HIGH {}),
+            Instruction::LDHFVx { reg } => Box::new(LDHFVx { reg }),
+            Instruction::LDRVx { reg } => Box::new(LDRVx { reg }),
+            Instruction::LDVxR { reg } => Box::new(LDVxR { reg }),
+            Instruction::LDILong { address } => Box::new(LDILong { address }),
+            Instruction::SAVEVxVy { reg_a, reg_b } => Box::new(SAVEVxVy { reg_a, reg_b }),
+            Instruction::LOADVxVy { reg_a, reg_b } => Box::new(LOADVxVy { reg_a, reg_b }),
+            Instruction::PLANE { planes } => Box::new(PLANE { planes }),
+            Instruction::AUDIO => Box::new(AUDIO {}),
+            Instruction::PITCHVx { reg } => Box::new(PITCHVx { reg }),
+        }
+    }
+
+    // Whether execution may continue somewhere else than at the next instruction
+    pub fn is_branch(&self) -> bool {
+        match self {
+            Instruction::JMP { .. }
+            | Instruction::CALL { .. }
+            | Instruction::RET
+            | Instruction::JPV0Offset { .. }
+            | Instruction::EXIT => true,
+            _ => self.is_skip(),
+        }
+    }
+
+    fn is_skip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::SEVxByte { .. }
+                | Instruction::SNEVxByte { .. }
+                | Instruction::SEVxVy { .. }
+                | Instruction::SNEVxVy { .. }
+                | Instruction::SKPKBRDVx { .. }
+                | Instruction::SKNPBRDVx { .. }
+        )
+    }
+
+    // The statically known addresses a branch at addr may continue at. Calls eventually return
+    // to the next instruction, skips may skip it. Targets only known at runtime (RET, JP V0) are
+    // missing, as is everything for instructions which aren't branches. Addresses past 0xFFFF
+    // wrap around, see Addressing for where they end up in a smaller memory.
+    // Note: A skip over a long XO-CHIP instruction actually skips 4 bytes, which is not known here.
+    pub fn branch_targets(&self, addr: u16) -> Vec<u16> {
+        match *self {
+            Instruction::JMP { address } => vec![address],
+            Instruction::CALL { address } => vec![address, addr.wrapping_add(2)],
+            _ if self.is_skip() => vec![addr.wrapping_add(2), addr.wrapping_add(4)],
+            _ => Vec::new(),
+        }
+    }
+
+    // The general purpose registers (V0 to VF) the instruction reads, in ascending order
+    pub fn reads_registers(&self) -> Vec<u8> {
+        let registers = match *self {
+            Instruction::SEVxByte { reg, .. }
+            | Instruction::SNEVxByte { reg, .. }
+            | Instruction::ADDVxByte { reg, .. }
+            | Instruction::LDDTVx { reg }
+            | Instruction::LDSTVx { reg }
+            | Instruction::ADDIVx { reg }
+            | Instruction::LDFVx { reg }
+            | Instruction::LDBVx { reg }
+            | Instruction::SKPKBRDVx { reg }
+            | Instruction::SKNPBRDVx { reg }
+            | Instruction::LDHFVx { reg }
+            | Instruction::PITCHVx { reg } => vec![reg],
+            Instruction::SEVxVy { reg_a, reg_b }
+            | Instruction::SNEVxVy { reg_a, reg_b }
+            | Instruction::ORVxVy { reg_a, reg_b }
+            | Instruction::ANDVxVy { reg_a, reg_b }
+            | Instruction::XORVxVy { reg_a, reg_b }
+            | Instruction::ADDVxVy { reg_a, reg_b }
+            | Instruction::SUBVxVy { reg_a, reg_b }
+            | Instruction::SUBNVxVy { reg_a, reg_b }
+            | Instruction::SHRVxVy { reg_a, reg_b }
+            | Instruction::SHLVxVy { reg_a, reg_b } => vec![reg_a, reg_b],
+            Instruction::LDVxVy { reg_b, .. } => vec![reg_b],
+            Instruction::DRW { reg_x, reg_y, .. } => vec![reg_x, reg_y],
+            Instruction::JPV0Offset { .. } => vec![0],
+            Instruction::LDIVx { reg } | Instruction::LDRVx { reg } => (0..=reg).collect(),
+            Instruction::SAVEVxVy { reg_a, reg_b } => register_span(reg_a, reg_b),
+            _ => Vec::new(),
+        };
+
+        sorted(registers)
+    }
+
+    // The general purpose registers (V0 to VF) the instruction may write, in ascending order. This
+    // includes VF when it is used as a flag, and for OR, AND and XOR, which reset it on the
+    // instruction sets with InstructionSet::logic_resets_vf.
+    pub fn writes_registers(&self) -> Vec<u8> {
+        let registers = match *self {
+            Instruction::LDVxByte { reg, .. }
+            | Instruction::ADDVxByte { reg, .. }
+            | Instruction::LDVxDT { reg }
+            | Instruction::LDVxK { reg }
+            | Instruction::RNDVxByte { reg, .. } => vec![reg],
+            Instruction::LDVxVy { reg_a, .. } => vec![reg_a],
+            Instruction::ORVxVy { reg_a, .. }
+            | Instruction::ANDVxVy { reg_a, .. }
+            | Instruction::XORVxVy { reg_a, .. }
+            | Instruction::ADDVxVy { reg_a, .. }
+            | Instruction::SUBVxVy { reg_a, .. }
+            | Instruction::SUBNVxVy { reg_a, .. }
+            | Instruction::SHRVxVy { reg_a, .. }
+            | Instruction::SHLVxVy { reg_a, .. } => vec![reg_a, 0xF],
+            Instruction::DRW { .. } => vec![0xF],
+            Instruction::LDVxI { reg } | Instruction::LDVxR { reg } => (0..=reg).collect(),
+            Instruction::LOADVxVy { reg_a, reg_b } => register_span(reg_a, reg_b),
+            _ => Vec::new(),
+        };
+
+        sorted(registers)
+    }
+
+    // The addresses of the RAM the instruction writes to, given the current value of I
+    pub fn writes_memory(&self, i: u16) -> Range<usize> {
+        let i = i as usize;
+        match *self {
+            Instruction::LDIVx { reg } => i..i + reg as usize + 1,
+            Instruction::LDBVx { .. } => i..i + 3,
+            Instruction::SAVEVxVy { reg_a, reg_b } => i..i + register_span(reg_a, reg_b).len(),
+            _ => i..i,
+        }
+    }
+}
+
+// The encodings of the instruction word: the selector in the highest nibble, followed by
+// an address, a register and a byte, or three nibbles
+fn with_address(sel: u16, address: u16) -> u16 {
+    sel << 12 | address & 0xFFF
+}
+
+fn with_byte(sel: u16, reg: u8, byte: u8) -> u16 {
+    sel << 12 | ((reg & 0xF) as u16) << 8 | byte as u16
+}
+
+fn with_nibbles(sel: u16, x: u8, y: u8, n: u8) -> u16 {
+    with_byte(sel, x, (y & 0xF) << 4 | n & 0xF)
+}
+
+fn register_span(reg_a: u8, reg_b: u8) -> Vec<u8> {
+    (std::cmp::min(reg_a, reg_b)..=std::cmp::max(reg_a, reg_b)).collect()
+}
+
+fn sorted(mut registers: Vec<u8>) -> Vec<u8> {
+    registers.sort_unstable();
+    registers.dedup();
+    registers
+}
+
+// The syntax FromStr parses, the Opcode structs display themselves the same way
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::InvalidOpcode { .. } => write!(f, "INVALID"),
+            Instruction::CLS => write!(f, "CLS"),
+            Instruction::RET => write!(f, "RET"),
+            Instruction::JMP { address } => write!(f, "JMP {:#X}", address),
+            Instruction::CALL { address } => write!(f, "CALL {:#X}", address),
+            Instruction::SEVxByte { reg, byte } => write!(f, "SE V{:X}, {:#X}", reg, byte),
+            Instruction::SNEVxByte { reg, byte } => write!(f, "SNE V{:X}, {:#X}", reg, byte),
+            Instruction::SEVxVy { reg_a, reg_b } => write!(f, "SE V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::SNEVxVy { reg_a, reg_b } => write!(f, "SNE V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::LDVxByte { reg, byte } => write!(f, "LD V{:X}, {:#X}", reg, byte),
+            Instruction::ADDVxByte { reg, byte } => write!(f, "ADD V{:X}, {:#X}", reg, byte),
+            Instruction::LDVxVy { reg_a, reg_b } => write!(f, "LD V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::ORVxVy { reg_a, reg_b } => write!(f, "OR V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::ANDVxVy { reg_a, reg_b } => write!(f, "AND V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::XORVxVy { reg_a, reg_b } => write!(f, "XOR V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::ADDVxVy { reg_a, reg_b } => write!(f, "ADD V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::SUBVxVy { reg_a, reg_b } => write!(f, "SUB V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::SHRVxVy { reg_a, reg_b } => write!(f, "SHR V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::SUBNVxVy { reg_a, reg_b } => {
+                write!(f, "SUBN V{:X}, V{:X}", reg_a, reg_b)
+            }
+            Instruction::SHLVxVy { reg_a, reg_b } => write!(f, "SHL V{:X}, V{:X}", reg_a, reg_b),
+            Instruction::LDVxDT { reg } => write!(f, "LD V{:X}, DT", reg),
+            Instruction::LDVxK { reg } => write!(f, "LD V{:X}, K", reg),
+            Instruction::LDDTVx { reg } => write!(f, "LD DT, V{:X}", reg),
+            Instruction::LDSTVx { reg } => write!(f, "LD ST, V{:X}", reg),
+            Instruction::ADDIVx { reg } => write!(f, "ADD I, V{:X}", reg),
+            Instruction::LDFVx { reg } => write!(f, "LD F, V{:X}", reg),
+            Instruction::LDBVx { reg } => write!(f, "LD B, V{:X}", reg),
+            Instruction::LDIVx { reg } => write!(f, "LD I, V{:X}", reg),
+            Instruction::LDVxI { reg } => write!(f, "LD V{:X}, I", reg),
+            Instruction::SKPKBRDVx { reg } => write!(f, "SKP V{:X}", reg),
+            Instruction::SKNPBRDVx { reg } => write!(f, "SKNP V{:X}", reg),
+            Instruction::DRW { reg_x, reg_y, size } => {
+                write!(f, "DRW V{:X}, V{:X}, {:#X}", reg_x, reg_y, size)
+            }
+            Instruction::RNDVxByte { reg, byte } => write!(f, "RND V{:X}, {:#X}", reg, byte),
+            Instruction::LDIAddr { address } => write!(f, "LDI {:#X}", address),
+            Instruction::JPV0Offset { address } => write!(f, "JP V0, {:#X}", address),
+            Instruction::SCD { rows } => write!(f, "SCD {:#X}", rows),
+            Instruction::SCR => write!(f, "SCR"),
+            Instruction::SCL => write!(f, "SCL"),
+            Instruction::EXIT => write!(f, "EXIT"),
+            Instruction::LOW => write!(f, "LOW"),
+            Instruction::HIGH => write!(f, "HIGH"),
+            Instruction::LDHFVx { reg } => write!(f, "LD HF, V{:X}", reg),
+            Instruction::LDRVx { reg } => write!(f, "LD R, V{:X}", reg),
+            Instruction::LDVxR { reg } => write!(f, "LD V{:X}, R", reg),
+            Instruction::LDILong { address } => write!(f, "LDIL {:#X}", address),
+            Instruction::SAVEVxVy { reg_a, reg_b } => {
+                write!(f, "SAVE V{:X}, V{:X}", reg_a, reg_b)
+            }
+            Instruction::LOADVxVy { reg_a, reg_b } => {
+                write!(f, "LOAD V{:X}, V{:X}", reg_a, reg_b)
+            }
+            Instruction::PLANE { planes } => write!(f, "PLANE {:#X}", planes),
+            Instruction::AUDIO => write!(f, "AUDIO"),
+            Instruction::PITCHVx { reg } => write!(f, "PITCH V{:X}", reg),
+        }
+    }
+}
+
+enum Operand<'a> {
+    Register(u8),
+    Number(u16),
+    Special(&'a str),
+}
+
+fn parse_operand(operand: &str) -> Result<Operand<'_>, String> {
+    if operand.len() == 2 && operand.starts_with('V') {
+        if let Ok(reg) = u8::from_str_radix(&operand[1..], 16) {
+            return Ok(Operand::Register(reg));
+        }
+    }
+
+    if operand.starts_with("0x") {
+        return u16::from_str_radix(operand.trim_start_matches("0x"), 16)
+            .map(Operand::Number)
+            .map_err(|_| format!("Invalid number {}", operand));
+    }
+
+    if operand.chars().all(|c| c.is_ascii_digit()) {
+        return operand
+            .parse::<u16>()
+            .map(Operand::Number)
+            .map_err(|_| format!("Invalid number {}", operand));
+    }
+
+    match operand {
+        "DT" | "ST" | "K" | "I" | "F" | "HF" | "B" | "R" => Ok(Operand::Special(operand)),
+        _ => Err(format!("Invalid operand {}", operand)),
+    }
+}
+
+// Parses the text produced by Display, e.g. "LD V1, 0x2A" or "DRW V0, V1, 0x5"
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (mnemonic, rest) = match s.find(char::is_whitespace) {
+            Some(idx) => (&s[..idx], s[idx..].trim()),
+            None => (s, ""),
+        };
+
+        let operands = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',')
+                .map(|operand| parse_operand(operand.trim()))
+                .collect::<Result<Vec<Operand>, String>>()?
+        };
+
+        use Operand::*;
+        let instruction = match (mnemonic, operands.as_slice()) {
+            ("CLS", []) => Instruction::CLS,
+            ("RET", []) => Instruction::RET,
+            ("SCR", []) => Instruction::SCR,
+            ("SCL", []) => Instruction::SCL,
+            ("EXIT", []) => Instruction::EXIT,
+            ("LOW", []) => Instruction::LOW,
+            ("HIGH", []) => Instruction::HIGH,
+            ("AUDIO", []) => Instruction::AUDIO,
+            ("JMP", [Number(address)]) if *address <= 0xFFF => {
+                Instruction::JMP { address: *address }
+            }
+            ("CALL", [Number(address)]) if *address <= 0xFFF => {
+                Instruction::CALL { address: *address }
+            }
+            ("LDI", [Number(address)]) if *address <= 0xFFF => {
+                Instruction::LDIAddr { address: *address }
+            }
+            ("LDIL", [Number(address)]) => Instruction::LDILong { address: *address },
+            ("JP", [Register(0), Number(address)]) if *address <= 0xFFF => {
+                Instruction::JPV0Offset { address: *address }
+            }
+            ("SCD", [Number(rows)]) if *rows <= 0xF => Instruction::SCD { rows: *rows as u8 },
+            ("PLANE", [Number(planes)]) if *planes <= 0xF => Instruction::PLANE {
+                planes: *planes as u8,
+            },
+            ("PITCH", [Register(reg)]) => Instruction::PITCHVx { reg: *reg },
+            ("SKP", [Register(reg)]) => Instruction::SKPKBRDVx { reg: *reg },
+            ("SKNP", [Register(reg)]) => Instruction::SKNPBRDVx { reg: *reg },
+            ("SE", [Register(reg), Number(byte)]) if *byte <= 0xFF => Instruction::SEVxByte {
+                reg: *reg,
+                byte: *byte as u8,
+            },
+            ("SNE", [Register(reg), Number(byte)]) if *byte <= 0xFF => Instruction::SNEVxByte {
+                reg: *reg,
+                byte: *byte as u8,
+            },
+            ("ADD", [Register(reg), Number(byte)]) if *byte <= 0xFF => Instruction::ADDVxByte {
+                reg: *reg,
+                byte: *byte as u8,
+            },
+            ("RND", [Register(reg), Number(byte)]) if *byte <= 0xFF => Instruction::RNDVxByte {
+                reg: *reg,
+                byte: *byte as u8,
+            },
+            ("LD", [Register(reg), Number(byte)]) if *byte <= 0xFF => Instruction::LDVxByte {
+                reg: *reg,
+                byte: *byte as u8,
+            },
+            ("SE", [Register(reg_a), Register(reg_b)]) => Instruction::SEVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("SNE", [Register(reg_a), Register(reg_b)]) => Instruction::SNEVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("LD", [Register(reg_a), Register(reg_b)]) => Instruction::LDVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("OR", [Register(reg_a), Register(reg_b)]) => Instruction::ORVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("AND", [Register(reg_a), Register(reg_b)]) => Instruction::ANDVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("XOR", [Register(reg_a), Register(reg_b)]) => Instruction::XORVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("ADD", [Register(reg_a), Register(reg_b)]) => Instruction::ADDVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("SUB", [Register(reg_a), Register(reg_b)]) => Instruction::SUBVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("SHR", [Register(reg_a), Register(reg_b)]) => Instruction::SHRVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("SUBN", [Register(reg_a), Register(reg_b)]) => Instruction::SUBNVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("SHL", [Register(reg_a), Register(reg_b)]) => Instruction::SHLVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("SAVE", [Register(reg_a), Register(reg_b)]) => Instruction::SAVEVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("LOAD", [Register(reg_a), Register(reg_b)]) => Instruction::LOADVxVy {
+                reg_a: *reg_a,
+                reg_b: *reg_b,
+            },
+            ("DRW", [Register(reg_x), Register(reg_y), Number(size)]) if *size <= 0xF => {
+                Instruction::DRW {
+                    reg_x: *reg_x,
+                    reg_y: *reg_y,
+                    size: *size as u8,
+                }
+            }
+            ("ADD", [Special("I"), Register(reg)]) => Instruction::ADDIVx { reg: *reg },
+            ("LD", [Register(reg), Special(special)]) => match *special {
+                "DT" => Instruction::LDVxDT { reg: *reg },
+                "K" => Instruction::LDVxK { reg: *reg },
+                "I" => Instruction::LDVxI { reg: *reg },
+                "R" => Instruction::LDVxR { reg: *reg },
+                _ => return Err(format!("Cannot load {} into a register", special)),
+            },
+            ("LD", [Special(special), Register(reg)]) => match *special {
+                "DT" => Instruction::LDDTVx { reg: *reg },
+                "ST" => Instruction::LDSTVx { reg: *reg },
+                "F" => Instruction::LDFVx { reg: *reg },
+                "HF" => Instruction::LDHFVx { reg: *reg },
+                "B" => Instruction::LDBVx { reg: *reg },
+                "I" => Instruction::LDIVx { reg: *reg },
+                "R" => Instruction::LDRVx { reg: *reg },
+                _ => return Err(format!("Cannot load a register into {}", special)),
+            },
+            _ => return Err(format!("Unknown instruction {}", s)),
+        };
+
+        Ok(instruction)
+    }
+}
+
+#[cfg(test)]
+mod tests;
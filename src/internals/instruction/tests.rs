@@ -0,0 +1,127 @@
+use super::*;
+
+// The enum has to encode and display itself exactly like the Opcode structs it stands for
+fn check_against_opcode(instruction: Instruction) {
+    let op = instruction.opcode();
+    assert_eq!(instruction.to_string(), op.to_string());
+    assert_eq!(instruction.size(), op.size(), "{}", instruction);
+    assert_eq!(instruction.encode_bytes(), op.encode(), "{}", instruction);
+    let (high, low) = op.assemble();
+    assert_eq!(instruction.encode(), u16::from_be_bytes([high, low]));
+}
+
+#[test]
+fn same_as_opcodes() {
+    for word in 0..=u16::MAX {
+        check_against_opcode(Instruction::decode(word));
+    }
+    check_against_opcode(Instruction::LDILong { address: 0xBEEF });
+}
+
+#[test]
+fn branch_targets_wrap() {
+    let skip = Instruction::SEVxByte { reg: 0, byte: 0 };
+    assert_eq!(skip.branch_targets(0x200), vec![0x202, 0x204]);
+    assert_eq!(skip.branch_targets(0xFFFE), vec![0x0000, 0x0002]);
+    let call = Instruction::CALL { address: 0x300 };
+    assert_eq!(call.branch_targets(0xFFFE), vec![0x300, 0x0000]);
+}
+
+#[test]
+fn parse_what_is_displayed() {
+    for word in 0..=u16::MAX {
+        let instruction = Instruction::decode(word);
+        if let Instruction::InvalidOpcode { .. } = instruction {
+            continue;
+        }
+        assert_eq!(instruction.to_string().parse(), Ok(instruction));
+    }
+    let long = Instruction::LDILong { address: 0xBEEF };
+    assert_eq!(long.to_string().parse(), Ok(long));
+
+    // Spacing and the case of hex digits in numbers don't matter
+    assert_eq!(
+        "  DRW VA,V1 ,  0xf ".parse(),
+        Ok(Instruction::DRW {
+            reg_x: 0xA,
+            reg_y: 1,
+            size: 0xF
+        })
+    );
+
+    let error = |text: &str| text.parse::<Instruction>().unwrap_err();
+    assert_eq!(error("NOP"), "Unknown instruction NOP");
+    assert_eq!(error("JMP 0x1000"), "Unknown instruction JMP 0x1000");
+    assert_eq!(error("LD V0, ST"), "Cannot load ST into a register");
+    assert_eq!(error("LD K, V0"), "Cannot load a register into K");
+}
+
+#[test]
+fn registers() {
+    let parse = |text: &str| text.parse::<Instruction>().unwrap();
+    let check = |text, reads: &[u8], writes: &[u8]| {
+        let instruction = parse(text);
+        assert_eq!(instruction.reads_registers(), reads, "{}", text);
+        assert_eq!(instruction.writes_registers(), writes, "{}", text);
+    };
+
+    check("CLS", &[], &[]);
+    check("LD V3, 0x10", &[], &[3]);
+    check("SE V3, 0x10", &[3], &[]);
+    check("LD V3, V1", &[1], &[3]);
+    check("SNE V3, V1", &[1, 3], &[]);
+    // VF is a flag, or reset by the logic_resets_vf quirk
+    check("ADD V3, V1", &[1, 3], &[3, 0xF]);
+    check("OR V3, V1", &[1, 3], &[3, 0xF]);
+    check("AND V3, V1", &[1, 3], &[3, 0xF]);
+    check("XOR V3, V1", &[1, 3], &[3, 0xF]);
+    check("SHL VF, VF", &[0xF], &[0xF]);
+    check("DRW V1, V2, 0x5", &[1, 2], &[0xF]);
+    check("JP V0, 0x300", &[0], &[]);
+    check("LD I, V2", &[0, 1, 2], &[]);
+    check("LD V2, I", &[], &[0, 1, 2]);
+    check("LD R, V1", &[0, 1], &[]);
+    check("LD V1, R", &[], &[0, 1]);
+    check("SAVE V4, V2", &[2, 3, 4], &[]);
+    check("LOAD V2, V4", &[], &[2, 3, 4]);
+    check("LD B, V7", &[7], &[]);
+    check("RND V7, 0xFF", &[], &[7]);
+}
+
+#[test]
+fn memory_writes() {
+    let parse = |text: &str| text.parse::<Instruction>().unwrap();
+    assert_eq!(parse("LD I, V2").writes_memory(0x300), 0x300..0x303);
+    assert_eq!(parse("LD B, V2").writes_memory(0x300), 0x300..0x303);
+    assert_eq!(parse("SAVE V4, V2").writes_memory(0x300), 0x300..0x303);
+    assert_eq!(parse("SAVE V2, V2").writes_memory(0x300), 0x300..0x301);
+    // Past the 16 bit addresses, for the memory to resolve
+    assert_eq!(parse("LD I, VF").writes_memory(0xFFFF), 0xFFFF..0x1000F);
+    assert!(parse("LD V2, I").writes_memory(0x300).is_empty());
+    assert!(parse("DRW V1, V2, 0x5").writes_memory(0x300).is_empty());
+}
+
+#[test]
+fn branches() {
+    let parse = |text: &str| text.parse::<Instruction>().unwrap();
+    for text in [
+        "JMP 0x300",
+        "CALL 0x300",
+        "RET",
+        "JP V0, 0x300",
+        "EXIT",
+        "SE V1, 0x2",
+        "SNE V1, 0x2",
+        "SE V1, V2",
+        "SNE V1, V2",
+        "SKP V1",
+        "SKNP V1",
+    ]
+    .iter()
+    {
+        assert!(parse(text).is_branch(), "{}", text);
+    }
+    for text in ["CLS", "LD V1, 0x2", "DRW V1, V2, 0x5", "LD V1, K", "SCR"].iter() {
+        assert!(!parse(text).is_branch(), "{}", text);
+    }
+}
@@ -2,15 +2,29 @@ use std::io::stdin;
 
 pub struct Keyboard {
     pub keys: [bool; 16],
+    // Whether Fx0A asks for the key on stdin (like the debugger does) instead of waiting until
+    // one of the keys is pressed (by a frontend)
+    pub line_input: bool,
 }
 
 impl Default for Keyboard {
     fn default() -> Self {
-        Keyboard { keys: [false; 16] }
+        Keyboard {
+            keys: [false; 16],
+            line_input: true,
+        }
     }
 }
 
 impl Keyboard {
+    // The lowest key that is currently held down
+    pub fn pressed_key(&self) -> Option<u8> {
+        self.keys
+            .iter()
+            .position(|&pressed| pressed)
+            .map(|key| key as u8)
+    }
+
     pub fn do_read(&mut self) {}
 
     pub fn blocking_read(&mut self) -> u8 {
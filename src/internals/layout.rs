@@ -0,0 +1,107 @@
+use crate::internals::display::LORES_HEIGHT;
+use crate::internals::processor::Processor;
+use std::ops::Range;
+use std::str::FromStr;
+
+// The end of the RAM of the COSMAC VIP, where its interpreter keeps its own state:
+// The return addresses growing downwards from 0xECF (two bytes each, big endian)
+pub const VIP_STACK: Range<usize> = 0xEA0..0xED0;
+// How many return addresses fit there, the entries of a deeper stack are not mirrored
+pub const VIP_STACK_DEPTH: usize = (VIP_STACK.end - VIP_STACK.start) / 2;
+// V0 to VF
+pub const VIP_REGISTERS: Range<usize> = 0xEF0..0xF00;
+// The 64x32 screen, 8 bytes per row and the leftmost pixel being the highest bit
+pub const VIP_DISPLAY: Range<usize> = 0xF00..0x1000;
+
+// How much of the machine state can be seen in the RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    // The RAM only holds the fonts and the program
+    Flat,
+    // The stack, the registers and the display are mapped into the RAM like on the COSMAC VIP,
+    // so programs peeking at (or poking into) them work. Only the first VIP_STACK_DEPTH entries
+    // of the stack fit.
+    Vip,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Layout::Flat),
+            "vip" => Ok(Layout::Vip),
+            _ => Err(format!("Unknown memory layout {}", s)),
+        }
+    }
+}
+
+// The address of the stack entry in the RAM of the VIP, the first one being at the end
+fn stack_entry(entry: usize) -> usize {
+    VIP_STACK.end - 2 * (entry + 1)
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+// Takes over what the program wrote into the mapped parts of the RAM, the written range being
+// the one of Instruction::writes_memory
+pub fn load(processor: &mut Processor, written: &Range<usize>) {
+    let memory = &mut processor.memory;
+    if overlaps(written, &VIP_STACK) {
+        let depth = std::cmp::min(memory.stack.len(), VIP_STACK_DEPTH);
+        for entry in 0..depth {
+            let addr = stack_entry(entry);
+            memory.stack[entry] = u16::from_be_bytes([memory.ram[addr], memory.ram[addr + 1]]);
+        }
+    }
+
+    if overlaps(written, &VIP_REGISTERS) {
+        memory
+            .registers
+            .v
+            .copy_from_slice(&memory.ram[VIP_REGISTERS]);
+    }
+
+    if overlaps(written, &VIP_DISPLAY) {
+        let display = &mut processor.display;
+        for (y, bytes) in memory.ram[VIP_DISPLAY].chunks_exact(8).enumerate() {
+            let mut row = [0; 8];
+            row.copy_from_slice(bytes);
+            display.bitplanes[0][y] = (u64::from_be_bytes(row) as u128) << 64;
+        }
+        display.mark_all_dirty();
+    }
+}
+
+// Writes the bytes to addr, only the decoded instructions covering the bytes which actually
+// changed are dropped from the cache (it is unlikely, but the program may execute them)
+fn mirror(processor: &mut Processor, addr: usize, bytes: &[u8]) {
+    for (addr, byte) in (addr..).zip(bytes.iter()) {
+        if processor.memory.ram[addr] != *byte {
+            processor.memory.ram[addr] = *byte;
+            processor.invalidate_decode_cache(addr as u16, 1);
+        }
+    }
+}
+
+// Mirrors the stack, the registers and the display into the RAM
+pub fn store(processor: &mut Processor) {
+    let depth = std::cmp::min(processor.memory.stack.len(), VIP_STACK_DEPTH);
+    for entry in 0..depth {
+        let ret = processor.memory.stack[entry];
+        mirror(processor, stack_entry(entry), &ret.to_be_bytes());
+    }
+
+    let v = processor.memory.registers.v;
+    mirror(processor, VIP_REGISTERS.start, &v);
+
+    for y in 0..LORES_HEIGHT {
+        let row = (processor.display.bitplanes[0][y] >> 64) as u64;
+        mirror(processor, VIP_DISPLAY.start + 8 * y, &row.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,80 @@
+use super::*;
+use crate::internals::instruction::Instruction;
+use crate::internals::variants::Variant;
+use std::rc::Rc;
+
+fn vip(program: &[u16]) -> Processor {
+    let mut processor = Processor::with_program(Variant::Chip8, program);
+    processor.layout = Layout::Vip;
+    processor.memory.stack = vec![0; 12];
+    processor
+}
+
+#[test]
+fn machine_state_is_mirrored() {
+    // CALL 0x204, V5 = 0x42, draw the 0 at (0, 0)
+    let mut p = vip(&[0x2204, 0x0000, 0x6542, 0xA050, 0xD005]);
+    for _ in 0..4 {
+        p.tick().unwrap();
+    }
+    assert_eq!(p.memory.ram[0xECE..0xED0], [0x02, 0x02]);
+    assert_eq!(p.memory.ram[0xEF5], 0x42);
+    assert_eq!(
+        p.memory.ram[0xF00..0xF10],
+        [0xF0, 0, 0, 0, 0, 0, 0, 0, 0x90, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn writes_are_taken_over() {
+    // Pokes 0xFF into the first byte of the screen and reads V0-V2 from the register area
+    let mut p = vip(&[0x60FF, 0xAF00, 0xF055, 0xAEF0, 0xF265]);
+    p.memory.ram[0xEF2] = 0x23;
+    for _ in 0..3 {
+        p.tick().unwrap();
+    }
+    assert_eq!(p.display.pixels().count(), 8);
+    // The register area is overwritten by the registers again
+    p.tick().unwrap();
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.v[..3], [0xFF, 0, 0]);
+
+    // A return address modified by the program
+    let mut p = vip(&[0x2204, 0x0000, 0xAECE, 0x6003, 0x6122, 0xF155, 0x00EE]);
+    for _ in 0..5 {
+        p.tick().unwrap();
+    }
+    assert_eq!(p.memory.stack[0], 0x0322);
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.pc, 0x0322);
+}
+
+#[test]
+fn only_changed_bytes_are_invalidated() {
+    let mut p = vip(&[0x6012, 0x6012]);
+    let register = p.decode_cached(0xEF0);
+    let screen = p.decode_cached(0xF80);
+
+    // V0 is mirrored to 0xEF0, the screen stays the same
+    p.tick().unwrap();
+    let changed = p.decode_cached(0xEF0);
+    assert!(!Rc::ptr_eq(&register, &changed));
+    assert_eq!(changed.instruction(), Instruction::decode(0x1200));
+    assert!(Rc::ptr_eq(&screen, &p.decode_cached(0xF80)));
+
+    // Storing the same value again keeps the cache
+    p.tick().unwrap();
+    assert!(Rc::ptr_eq(&changed, &p.decode_cached(0xEF0)));
+}
+
+#[test]
+fn deeper_stacks_are_cut_off() {
+    let mut p = vip(&[0x6000]);
+    p.memory.stack = (0..30).map(|entry| 0x300 + entry).collect();
+    p.tick().unwrap();
+    assert_eq!(p.memory.ram[0xECE..0xED0], [0x03, 0x00]);
+    assert_eq!(p.memory.ram[0xEA0..0xEA2], [0x03, 0x17]);
+    assert!(p.memory.ram[0xE00..VIP_STACK.start]
+        .iter()
+        .all(|&byte| byte == 0));
+}
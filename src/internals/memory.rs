@@ -1,16 +1,78 @@
+use crate::internals::fault::Fault;
+use crate::internals::font::{Font, FONT_ADDR, FONT_LEN};
+use std::convert::TryFrom;
 use std::fmt;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
+use std::str::FromStr;
 
-#[derive(Debug)]
+// Errors of the running program when it accesses the memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryError {
+    // The first address of an access which lies outside of the memory
+    OutOfBounds { addr: usize },
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::OutOfBounds { addr } => write!(
+                f,
+                "Segmentation Fault: Tried to access address {:#X}, which is outside of the memory",
+                addr
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+// How addresses computed by the program (pc, I and the offsets from it) map onto the memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Addressing {
+    // Every address past the end of the memory is an error
+    Strict,
+    // Addresses are 12 bits wide like on the original hardware, so 0x1000 is 0x000 again
+    Wrap12,
+    // XO-CHIP: Addresses are 16 bits wide and wrap at 0xFFFF
+    Extended,
+}
+
+impl Addressing {
+    // Cuts addr down to the width of an address, which doesn't make it part of the memory yet
+    pub fn mask(self, addr: usize) -> usize {
+        match self {
+            Addressing::Strict => addr,
+            Addressing::Wrap12 => addr & 0xFFF,
+            Addressing::Extended => addr & 0xFFFF,
+        }
+    }
+}
+
+impl FromStr for Addressing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Addressing::Strict),
+            "wrap" => Ok(Addressing::Wrap12),
+            "extended" => Ok(Addressing::Extended),
+            _ => Err(format!("Unknown addressing mode {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Registers {
-    // program counter (stores the current instruction) allowed values from 0 to 4096
+    // program counter (stores the address of the current instruction). It may hold any 16 bit
+    // address, see Addressing for how it maps onto the 4 KiB (or XO-CHIP's 64 KiB) of memory.
     pub pc: u16,
 
-    // stack pointer, stack is pretty limited on CHIP-8 [16 16bit values], thus only 16 levels of
-    // recursion and they only store ret addresses (hence 16 bit). Initialized to -1, because this
-    // always points to the top / last pushed element, which is -1 when nothing has been pushed yet
+    // stack pointer, stack is pretty limited on CHIP-8 (see InstructionSet::stack_depth), thus only
+    // a few levels of recursion and they only store ret addresses (hence 16 bit). Initialized to -1,
+    // because this always points to the top / last pushed element, which is -1 when nothing has
+    // been pushed yet
     pub sp: i8,
 
     // This register is generally used to store memory addresses, so only the lowest
@@ -25,6 +87,14 @@ pub struct Registers {
 
     // general purpose registers, but VF (V[15]) is used as a special flag by some instructions
     pub v: [u8; 16],
+
+    // The SUPER-CHIP "RPL user flags" (HP-48 calculator registers), only accessible via Fx75/Fx85.
+    // SUPER-CHIP only has 8 of them, XO-CHIP extends them to 16.
+    pub rpl: [u8; 16],
+
+    // XO-CHIP audio: a 128 bit (1-bit sample) pattern buffer, loaded by F002, and its playback pitch
+    pub pattern: [u8; 16],
+    pub pitch: u8,
 }
 
 impl Default for Registers {
@@ -34,6 +104,9 @@ impl Default for Registers {
             sp: -1,
             i: 0,
             v: [0; 16],
+            rpl: [0; 16],
+            pattern: [0; 16],
+            pitch: 64, // 4000 Hz playback rate
             dt: 0,
             st: 0,
         }
@@ -73,112 +146,169 @@ impl fmt::Display for Registers {
 
 pub struct Memory {
     pub registers: Registers,
-    pub stack: [u16; 16],
-    pub ram: [u8; 4096],
+    // The return addresses, as many entries as subroutine calls can be nested
+    pub stack: Vec<u16>,
+    // 4 KiB for CHIP-8 and SUPER-CHIP, 64 KiB for XO-CHIP
+    pub ram: Vec<u8>,
+    // See InstructionSet::addressing
+    pub addressing: Addressing,
+    // Where LDFVx finds the hex font, see Processor::set_font
+    pub font_addr: u16,
 }
 
 impl Default for Memory {
     fn default() -> Self {
-        let mut mem = Memory {
-            stack: [0; 16],
-            registers: Registers::default(),
-            ram: [0; 4096],
-        };
-
-        let digit_0 = [0xF0, 0x90, 0x90, 0x90, 0xF0];
-        let digit_1 = [0x20, 0x60, 0x20, 0x20, 0x70];
-        let digit_2 = [0xF0, 0x10, 0xF0, 0x80, 0xF0];
-        let digit_3 = [0xF0, 0x10, 0xF0, 0x10, 0xF0];
-        let digit_4 = [0x90, 0x90, 0xF0, 0x10, 0x10];
-        let digit_5 = [0xF0, 0x80, 0xF0, 0x10, 0xF0];
-        let digit_6 = [0xF0, 0x80, 0xF0, 0x90, 0xF0];
-        let digit_7 = [0xF0, 0x10, 0x20, 0x40, 0x40];
-        let digit_8 = [0xF0, 0x90, 0xF0, 0x10, 0xF0];
-        let digit_9 = [0xF0, 0x90, 0xF0, 0x10, 0xF0];
-        let digit_a = [0xF0, 0x90, 0xF0, 0x90, 0x90];
-        let digit_b = [0xE0, 0x90, 0xE0, 0x90, 0xF0];
-        let digit_c = [0xF0, 0x80, 0x80, 0x80, 0xF0];
-        let digit_d = [0xE0, 0x90, 0x90, 0x90, 0xE0];
-        let digit_e = [0xF0, 0x80, 0xF0, 0x80, 0xF0];
-        let digit_f = [0xF0, 0x80, 0xF0, 0x80, 0x80];
-
-        for i in 0..5 {
-            mem.ram[i] = digit_0[i];
-            mem.ram[6 + i] = digit_1[i];
-            mem.ram[2 * 6 + i] = digit_2[i];
-            mem.ram[3 * 6 + i] = digit_3[i];
-            mem.ram[4 * 6 + i] = digit_4[i];
-            mem.ram[5 * 6 + i] = digit_5[i];
-            mem.ram[6 * 6 + i] = digit_6[i];
-            mem.ram[7 * 6 + i] = digit_7[i];
-            mem.ram[8 * 6 + i] = digit_8[i];
-            mem.ram[9 * 6 + i] = digit_9[i];
-            mem.ram[0xA * 6 + i] = digit_a[i];
-            mem.ram[0xB * 6 + i] = digit_b[i];
-            mem.ram[0xC * 6 + i] = digit_c[i];
-            mem.ram[0xD * 6 + i] = digit_d[i];
-            mem.ram[0xE * 6 + i] = digit_e[i];
-            mem.ram[0xF * 6 + i] = digit_f[i];
-        }
-
+        let mut mem = Memory::with_size(4096);
+        mem.load_font(&Font::default())
+            .expect("The font fits into the memory");
         mem
     }
 }
 
 impl Memory {
-    pub fn load_from_file(&mut self, name: &str, loading_point: u16) -> Result<(), io::Error> {
-        if loading_point >= 4096 {
-            panic!("Loading Point exceeds memory range [0, 4096]");
+    // An empty memory without any font, see InstructionSet::load_fonts
+    pub fn with_size(size: usize) -> Memory {
+        Memory {
+            stack: vec![0; 16],
+            registers: Registers::default(),
+            ram: vec![0; size],
+            addressing: Addressing::Wrap12,
+            font_addr: FONT_ADDR,
         }
+    }
 
+    // The SUPER-CHIP big font directly follows the hex font
+    pub fn big_font_addr(&self) -> usize {
+        self.font_addr as usize + FONT_LEN
+    }
+
+    pub fn load_font(&mut self, font: &Font) -> Result<(), MemoryError> {
+        self.write_slice(self.font_addr, font.small.as_flattened())
+    }
+
+    pub fn load_big_font(&mut self, font: &Font) -> Result<(), MemoryError> {
+        let addr = self.big_font_addr();
+        let start = u16::try_from(addr).map_err(|_| MemoryError::OutOfBounds { addr })?;
+        self.write_slice(start, font.big.as_flattened())
+    }
+
+    pub fn load_from_file(&mut self, name: &str, loading_point: u16) -> Result<(), io::Error> {
         let mut buffer = Vec::new();
         File::open(name)?.read_to_end(&mut buffer)?;
+        self.load(&buffer, loading_point);
+        Ok(())
+    }
+
+    pub fn load(&mut self, data: &[u8], loading_point: u16) {
+        if loading_point as usize >= self.ram.len() {
+            panic!("Loading Point exceeds memory range [0, {}]", self.ram.len());
+        }
 
-        if buffer.len() > 4096 - loading_point as usize {
+        if self.write_slice(loading_point, data).is_err() {
             panic!("Image too large to fit into memory");
         }
+    }
 
-        // Move buffer into ram at a given loading_point. There got to be a more rusty way for this,
-        // but array.from_slice() does not support the offset of the loading_point
+    // Where addr ends up according to the addressing mode, but always inside of the memory. This
+    // is meant for bookkeeping like the decode cache, accesses of the program go through resolve.
+    pub fn wrap(&self, addr: usize) -> u16 {
+        (self.addressing.mask(addr) % self.ram.len()) as u16
+    }
 
-        for (i, buf) in buffer.iter().enumerate() {
-            self.ram[loading_point as usize + i] = *buf;
-        }
+    // What a 16 bit register like pc or I holds when set to addr. Unlike resolve, this may lie
+    // past the end of the memory, but with strict addressing it can't be past 0xFFFF.
+    pub fn register_value(&self, addr: usize) -> Result<u16, MemoryError> {
+        u16::try_from(self.addressing.mask(addr)).map_err(|_| MemoryError::OutOfBounds { addr })
+    }
 
-        Ok(())
+    // The address the program accesses with addr, an error if it isn't part of the memory
+    pub fn resolve(&self, addr: usize) -> Result<u16, MemoryError> {
+        let resolved = self.addressing.mask(addr);
+        if resolved < self.ram.len() {
+            Ok(resolved as u16)
+        } else {
+            Err(MemoryError::OutOfBounds { addr })
+        }
     }
 
-    pub fn read_u8(&mut self, addr: u16) -> u8 {
-        if addr > 4096 {
-            panic!("Segmentation Fault: Tried to read from address {}", addr);
+    // The range of len bytes starting at addr, if all of them are part of the memory
+    pub fn range(&self, addr: u16, len: usize) -> Result<std::ops::Range<usize>, MemoryError> {
+        let start = addr as usize;
+        match start.checked_add(len) {
+            Some(end) if end <= self.ram.len() => Ok(start..end),
+            _ => Err(MemoryError::OutOfBounds {
+                addr: std::cmp::max(start, self.ram.len()),
+            }),
         }
+    }
 
-        self.ram[addr as usize]
+    pub fn read_u8(&self, addr: u16) -> Result<u8, MemoryError> {
+        Ok(self.read_slice(addr, 1)?[0])
     }
 
-    pub fn read_two_u8(&mut self, addr: u16) -> (u8, u8) {
-        (self.read_u8(addr), self.read_u8(addr + 1))
+    // Big-endian, like the instructions
+    pub fn read_u16(&self, addr: u16) -> Result<u16, MemoryError> {
+        let bytes = self.read_slice(addr, 2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
     }
 
-    pub fn read_u16(&mut self, addr: u16) -> u16 {
-        let (x, y) = self.read_two_u8(addr);
-        //x as u16 >> 16 | y as u16
-        x as u16 >> 8 | y as u16
+    pub fn read_slice(&self, addr: u16, len: usize) -> Result<&[u8], MemoryError> {
+        let range = self.range(addr, len)?;
+        Ok(&self.ram[range])
     }
 
-    pub fn stack_push(&mut self, val: u16) {
+    pub fn write_u8(&mut self, addr: u16, value: u8) -> Result<(), MemoryError> {
+        self.write_slice(addr, &[value])
+    }
+
+    pub fn write_slice(&mut self, addr: u16, data: &[u8]) -> Result<(), MemoryError> {
+        let range = self.range(addr, data.len())?;
+        self.ram[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    // Fetches the instruction word at addr. Unless the addressing is strict, an instruction in the
+    // last byte of the memory continues at its beginning, see Processor::tick for the checks.
+    pub fn read_two_u8(&self, addr: u16) -> (u8, u8) {
+        let addr = self.wrap(addr as usize);
+        let next = self.wrap(addr as usize + 1);
+        (self.ram[addr as usize], self.ram[next as usize])
+    }
+
+    pub fn stack_push(&mut self, val: u16) -> Result<(), Fault> {
+        let sp = (self.registers.sp + 1) as usize;
+        if sp >= self.stack.len() {
+            return Err(Fault::StackOverflow {
+                depth: self.stack.len(),
+            });
+        }
+
+        self.stack[sp] = val;
         self.registers.sp += 1;
-        self.stack[self.registers.sp as usize] = val;
+        Ok(())
     }
 
-    pub fn stack_pop(&mut self) -> u16 {
-        let peek = self.stack_peek();
+    pub fn stack_pop(&mut self) -> Result<u16, Fault> {
+        let peek = self.stack_peek()?;
         self.registers.sp -= 1;
-        peek
+        Ok(peek)
     }
 
-    pub fn stack_peek(&mut self) -> u16 {
-        self.stack[self.registers.sp as usize]
+    pub fn stack_peek(&self) -> Result<u16, Fault> {
+        if self.registers.sp < 0 {
+            return Err(Fault::StackUnderflow);
+        }
+        Ok(self.stack[self.registers.sp as usize])
+    }
+
+    // The addresses of the CALL instructions that lead to the current subroutine, innermost first
+    pub fn backtrace(&self) -> Vec<u16> {
+        let len = (self.registers.sp + 1) as usize;
+        self.stack[..len]
+            .iter()
+            .rev()
+            .map(|&ret| self.wrap(ret as usize + self.ram.len() - 2))
+            .collect()
     }
 }
 
@@ -191,3 +321,6 @@ impl fmt::Debug for Memory {
         )
     }
 }
+
+#[cfg(test)]
+mod tests;
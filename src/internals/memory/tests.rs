@@ -0,0 +1,42 @@
+use super::*;
+
+#[test]
+fn read_u16_is_big_endian() {
+    let mut memory = Memory::with_size(4096);
+    memory.write_slice(0x300, &[0x12, 0x34]).unwrap();
+    assert_eq!(memory.read_u16(0x300), Ok(0x1234));
+    assert_eq!(memory.read_u8(0x301), Ok(0x34));
+}
+
+#[test]
+fn accesses_outside_of_the_memory() {
+    let mut memory = Memory::with_size(4096);
+    let out_of_bounds = |addr| MemoryError::OutOfBounds { addr };
+
+    assert_eq!(memory.read_u8(0xFFF), Ok(0));
+    assert_eq!(memory.read_u8(0x1000).unwrap_err(), out_of_bounds(0x1000));
+    assert_eq!(memory.read_u16(0xFFF).unwrap_err(), out_of_bounds(0x1000));
+    assert_eq!(memory.read_slice(0xFFC, 4).map(|bytes| bytes.len()), Ok(4));
+    assert_eq!(
+        memory.read_slice(0xFFC, 5).unwrap_err(),
+        out_of_bounds(0x1000)
+    );
+    assert_eq!(
+        memory.write_u8(0xFFFF, 1).unwrap_err(),
+        out_of_bounds(0xFFFF)
+    );
+    assert_eq!(
+        memory.write_slice(0xFFE, &[1, 2, 3]).unwrap_err(),
+        out_of_bounds(0x1000)
+    );
+    // Nothing is written when any byte lies outside
+    assert_eq!(memory.read_slice(0xFFE, 2), Ok(&[0, 0][..]));
+}
+
+#[test]
+fn instruction_fetch_wraps_around() {
+    let mut memory = Memory::with_size(4096);
+    memory.write_u8(0xFFF, 0x12).unwrap();
+    memory.write_u8(0x000, 0x34).unwrap();
+    assert_eq!(memory.read_two_u8(0xFFF), (0x12, 0x34));
+}
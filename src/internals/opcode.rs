@@ -1,16 +1,39 @@
+use crate::internals::fault::Fault;
+use crate::internals::font::{BIG_GLYPH_LEN, GLYPH_LEN};
+use crate::internals::instruction::Instruction;
+use crate::internals::memory::MemoryError;
 use crate::internals::processor::Processor;
 use downcast_rs::Downcast;
-use rand::Rng;
 use std::fmt;
 
 pub trait Opcode: fmt::Debug + fmt::Display + Downcast {
-    fn execute(&self, processor: &mut Processor);
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault>;
     fn modified_pc(&self) -> bool {
         false // The majority does not tamper with the PC
     }
     fn assemble(&self) -> (u8, u8) {
         (0x0, 0x0) // To easier migrate, return an invalid OPCode
     }
+    // The length of the instruction in bytes, only XO-CHIP's F000 NNNN is longer than one word
+    fn size(&self) -> u16 {
+        2
+    }
+    fn encode(&self) -> Vec<u8> {
+        let (high, low) = self.assemble();
+        vec![high, low]
+    }
+    // The opcode as a value that can be pattern-matched, instead of downcasting the trait object
+    fn instruction(&self) -> Instruction {
+        let (high, low) = self.assemble();
+        Instruction::decode(u16::from_be_bytes([high, low]))
+    }
+    // The error for executing this opcode when the machine cannot
+    fn invalid(&self) -> Fault {
+        let (high, low) = self.assemble();
+        Fault::InvalidInstruction {
+            opcode: u16::from_be_bytes([high, low]),
+        }
+    }
 }
 impl_downcast!(Opcode);
 
@@ -177,11 +200,64 @@ pub struct JPV0Offset {
     pub address: u16,
 }
 
+// SUPER-CHIP Opcodes
+#[derive(Debug)]
+pub struct SCD {
+    pub rows: u8,
+}
+#[derive(Debug)]
+pub struct SCR {}
+#[derive(Debug)]
+pub struct SCL {}
+#[derive(Debug)]
+pub struct EXIT {}
+#[derive(Debug)]
+pub struct LOW {}
+#[derive(Debug)]
+pub struct HIGH {}
+#[derive(Debug)]
+pub struct LDHFVx {
+    pub reg: u8,
+}
+#[derive(Debug)]
+pub struct LDRVx {
+    pub reg: u8,
+}
+#[derive(Debug)]
+pub struct LDVxR {
+    pub reg: u8,
+}
+
+// XO-CHIP Opcodes
+#[derive(Debug)]
+pub struct LDILong {
+    pub address: u16,
+}
+#[derive(Debug)]
+pub struct SAVEVxVy {
+    pub reg_a: u8,
+    pub reg_b: u8,
+}
+#[derive(Debug)]
+pub struct LOADVxVy {
+    pub reg_a: u8,
+    pub reg_b: u8,
+}
+#[derive(Debug)]
+pub struct PLANE {
+    pub planes: u8,
+}
+#[derive(Debug)]
+pub struct AUDIO {}
+#[derive(Debug)]
+pub struct PITCHVx {
+    pub reg: u8,
+}
+
 impl Opcode for CLS {
-    fn execute(&self, processor: &mut Processor) {
-        for i in 0..64 * 32 {
-            processor.display.screen[i] = false;
-        }
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.display.clear();
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -196,13 +272,9 @@ impl fmt::Display for CLS {
 }
 
 impl Opcode for RET {
-    fn execute(&self, processor: &mut Processor) {
-        let pop = processor.memory.stack_pop();
-        println!(
-            "Returning from {:#X} to {:#X}",
-            processor.memory.registers.pc, pop
-        );
-        processor.memory.registers.pc = pop;
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.memory.registers.pc = processor.memory.stack_pop()?;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -221,8 +293,19 @@ impl fmt::Display for RET {
 }
 
 impl Opcode for InvalidOpcode {
-    fn execute(&self, _processor: &mut Processor) {
-        panic!("Unknown Opcode: {:#X} {:#X}", self.opcode.0, self.opcode.1)
+    fn execute(&self, _processor: &mut Processor) -> Result<(), Fault> {
+        Err(self.invalid())
+    }
+
+    // Probably data, so keep it as it is
+    fn assemble(&self) -> (u8, u8) {
+        self.opcode
+    }
+
+    fn instruction(&self) -> Instruction {
+        Instruction::InvalidOpcode {
+            opcode: u16::from_be_bytes([self.opcode.0, self.opcode.1]),
+        }
     }
 }
 
@@ -241,12 +324,10 @@ impl JMP {
 }
 
 impl Opcode for JMP {
-    fn execute(&self, processor: &mut Processor) {
-        if self.address + 2 == processor.memory.registers.pc {
-            panic!("Useless infinite loop detected, jmp jumps to itself");
-        }
-        //println!("Jumping to {:#X}", self.address);
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        // Jumping to itself is how most programs wait forever once they are done
         processor.memory.registers.pc = self.address;
+        Ok(())
     }
 
     fn modified_pc(&self) -> bool {
@@ -282,12 +363,13 @@ impl fmt::Display for CALL {
 }
 
 impl Opcode for CALL {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         //println!("Calling {:#X}", self.address);
-        processor
-            .memory
-            .stack_push(processor.memory.registers.pc + 2);
+        let memory = &processor.memory;
+        let next = memory.register_value(memory.registers.pc as usize + 2)?;
+        processor.memory.stack_push(next)?;
         processor.memory.registers.pc = self.address;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -311,8 +393,9 @@ impl LDIAddr {
 }
 
 impl Opcode for LDIAddr {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         processor.memory.registers.i = self.address;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -338,22 +421,40 @@ impl JPV0Offset {
 }
 
 impl Opcode for JPV0Offset {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!()
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let reg = if processor.instruction_set.jump_reads_vx() {
+            (self.address >> 8) as usize
+        } else {
+            0
+        };
+        processor.memory.registers.pc = self.address + processor.memory.registers.v[reg] as u16;
+        Ok(())
+    }
+
+    fn modified_pc(&self) -> bool {
+        true
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (
+            (0xB << 4 | (self.address & 0xF00) >> 8) as u8,
+            (self.address & 0xFF) as u8,
+        )
     }
 }
 
 impl fmt::Display for JPV0Offset {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "JPV0Offset {:#X}", self.address)
+        write!(f, "JP V0, {:#X}", self.address)
     }
 }
 
 impl Opcode for SEVxByte {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         if processor.memory.registers.v[self.reg as usize] == self.byte {
-            processor.memory.registers.pc += 2;
+            processor.skip_instruction()?;
         }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -368,10 +469,11 @@ impl fmt::Display for SEVxByte {
 }
 
 impl Opcode for SNEVxByte {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         if processor.memory.registers.v[self.reg as usize] != self.byte {
-            processor.memory.registers.pc += 2;
+            processor.skip_instruction()?;
         }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -385,10 +487,13 @@ impl fmt::Display for SNEVxByte {
     }
 }
 
-//@TODO: Implement and fix
 impl Opcode for SEVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let registers = &processor.memory.registers;
+        if registers.v[self.reg_a as usize] == registers.v[self.reg_b as usize] {
+            processor.skip_instruction()?;
+        }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -403,8 +508,9 @@ impl fmt::Display for SEVxVy {
 }
 
 impl Opcode for LDVxByte {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         processor.memory.registers.v[self.reg as usize] = self.byte;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -419,8 +525,11 @@ impl fmt::Display for LDVxByte {
 }
 
 impl Opcode for ADDVxByte {
-    fn execute(&self, processor: &mut Processor) {
-        processor.memory.registers.v[self.reg as usize] += self.byte;
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        // Doesn't touch VF, the carry is lost
+        let registers = &mut processor.memory.registers;
+        registers.v[self.reg as usize] = registers.v[self.reg as usize].wrapping_add(self.byte);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -435,8 +544,10 @@ impl fmt::Display for ADDVxByte {
 }
 
 impl Opcode for LDVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.memory.registers.v[self.reg_a as usize] =
+            processor.memory.registers.v[self.reg_b as usize];
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -451,8 +562,9 @@ impl fmt::Display for LDVxVy {
 }
 
 impl Opcode for ORVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        logic(processor, self.reg_a, self.reg_b, |a, b| a | b);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -467,8 +579,9 @@ impl fmt::Display for ORVxVy {
 }
 
 impl Opcode for ANDVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        logic(processor, self.reg_a, self.reg_b, |a, b| a & b);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -483,8 +596,9 @@ impl fmt::Display for ANDVxVy {
 }
 
 impl Opcode for XORVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        logic(processor, self.reg_a, self.reg_b, |a, b| a ^ b);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -498,17 +612,38 @@ impl fmt::Display for XORVxVy {
     }
 }
 
-impl Opcode for ADDVxVy {
-    fn execute(&self, processor: &mut Processor) {
-        let res: u16 = processor.memory.registers.v[self.reg_a as usize] as u16
-            + processor.memory.registers.v[self.reg_b as usize] as u16;
-        if res > 255 {
-            processor.memory.registers.v[0xF as usize] = 1;
-        } else {
-            processor.memory.registers.v[0xF as usize] = 0;
-        }
+// Stores the result of an arithmetic instruction and its flag. The flag is written last, so it
+// wins when VF is the destination.
+fn set_with_flag(processor: &mut Processor, reg: u8, value: u8, flag: bool) {
+    processor.memory.registers.v[reg as usize] = value;
+    processor.memory.registers.v[0xF] = flag as u8;
+}
+
+// 8xy1, 8xy2 and 8xy3
+fn logic(processor: &mut Processor, reg_a: u8, reg_b: u8, op: fn(u8, u8) -> u8) {
+    let registers = &mut processor.memory.registers;
+    registers.v[reg_a as usize] = op(registers.v[reg_a as usize], registers.v[reg_b as usize]);
+    if processor.instruction_set.logic_resets_vf() {
+        registers.v[0xF] = 0;
+    }
+}
 
-        processor.memory.registers.v[self.reg_a as usize] = (res & 0x00FF) as u8;
+// The value 8xy6 and 8xyE shift, see InstructionSet::shift_reads_vy
+fn shift_source(processor: &Processor, reg_a: u8, reg_b: u8) -> u8 {
+    if processor.instruction_set.shift_reads_vy() {
+        processor.memory.registers.v[reg_b as usize]
+    } else {
+        processor.memory.registers.v[reg_a as usize]
+    }
+}
+
+impl Opcode for ADDVxVy {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let registers = &processor.memory.registers;
+        let (res, carry) =
+            registers.v[self.reg_a as usize].overflowing_add(registers.v[self.reg_b as usize]);
+        set_with_flag(processor, self.reg_a, res, carry);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -523,16 +658,13 @@ impl fmt::Display for ADDVxVy {
 }
 
 impl Opcode for SUBVxVy {
-    fn execute(&self, processor: &mut Processor) {
-        let res: i16 = processor.memory.registers.v[self.reg_a as usize] as i16
-            - processor.memory.registers.v[self.reg_b as usize] as i16;
-        if res < 0 {
-            processor.memory.registers.v[0xF as usize] = 1;
-        } else {
-            processor.memory.registers.v[0xF as usize] = 0;
-        }
-
-        processor.memory.registers.v[self.reg_a as usize] = (std::cmp::max(res, 0) & 0x00FF) as u8;
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        // VF is set when there is no borrow
+        let registers = &processor.memory.registers;
+        let (res, borrow) =
+            registers.v[self.reg_a as usize].overflowing_sub(registers.v[self.reg_b as usize]);
+        set_with_flag(processor, self.reg_a, res, !borrow);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -547,8 +679,10 @@ impl fmt::Display for SUBVxVy {
 }
 
 impl Opcode for SHRVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let value = shift_source(processor, self.reg_a, self.reg_b);
+        set_with_flag(processor, self.reg_a, value >> 1, value & 1 == 1);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -563,8 +697,12 @@ impl fmt::Display for SHRVxVy {
 }
 
 impl Opcode for SUBNVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let registers = &processor.memory.registers;
+        let (res, borrow) =
+            registers.v[self.reg_b as usize].overflowing_sub(registers.v[self.reg_a as usize]);
+        set_with_flag(processor, self.reg_a, res, !borrow);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -579,8 +717,10 @@ impl fmt::Display for SUBNVxVy {
 }
 
 impl Opcode for SHLVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let value = shift_source(processor, self.reg_a, self.reg_b);
+        set_with_flag(processor, self.reg_a, value << 1, value & 0x80 != 0);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -595,8 +735,16 @@ impl fmt::Display for SHLVxVy {
 }
 
 impl Opcode for SNEVxVy {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let registers = &processor.memory.registers;
+        if registers.v[self.reg_a as usize] != registers.v[self.reg_b as usize] {
+            processor.skip_instruction()?;
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (9 << 4 | self.reg_a, self.reg_b << 4)
     }
 }
 
@@ -607,10 +755,9 @@ impl fmt::Display for SNEVxVy {
 }
 
 impl Opcode for LDVxDT {
-    fn execute(&self, processor: &mut Processor) {
-        //@FIXME @TODO: Currently we have no feeling for time, especially not when stepping through, so we just decrease the timer every time it is accessed
-        processor.memory.registers.dt -= 1;
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         processor.memory.registers.v[self.reg as usize] = processor.memory.registers.dt;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -625,8 +772,9 @@ impl fmt::Display for LDVxDT {
 }
 
 impl Opcode for LDDTVx {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         processor.memory.registers.dt = processor.memory.registers.v[self.reg as usize];
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -641,8 +789,22 @@ impl fmt::Display for LDDTVx {
 }
 
 impl Opcode for LDVxK {
-    fn execute(&self, processor: &mut Processor) {
-        processor.memory.registers.v[self.reg as usize] = processor.keyboard.blocking_read();
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        if processor.keyboard.line_input {
+            processor.memory.registers.v[self.reg as usize] = processor.keyboard.blocking_read();
+            return Ok(());
+        }
+
+        // Without a key pressed, this instruction is executed again, so the program waits
+        match processor.keyboard.pressed_key() {
+            Some(key) => processor.memory.registers.v[self.reg as usize] = key,
+            None => {
+                let pc = processor.memory.registers.pc as usize;
+                processor.memory.registers.pc =
+                    processor.memory.wrap(pc + processor.memory.ram.len() - 2)
+            }
+        }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -657,8 +819,9 @@ impl fmt::Display for LDVxK {
 }
 
 impl Opcode for LDSTVx {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!()
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.memory.registers.st = processor.memory.registers.v[self.reg as usize];
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -668,13 +831,16 @@ impl Opcode for LDSTVx {
 
 impl fmt::Display for LDSTVx {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "TODO LDSTVx")
+        write!(f, "LD ST, V{:X}", self.reg)
     }
 }
 
 impl Opcode for ADDIVx {
-    fn execute(&self, processor: &mut Processor) {
-        processor.memory.registers.i += processor.memory.registers.v[self.reg as usize] as u16;
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let memory = &mut processor.memory;
+        let i = memory.registers.i as usize + memory.registers.v[self.reg as usize] as usize;
+        memory.registers.i = memory.register_value(i)?;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -684,21 +850,21 @@ impl Opcode for ADDIVx {
 
 impl fmt::Display for ADDIVx {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "TODO ADDIVx")
+        write!(f, "ADD I, V{:X}", self.reg)
     }
 }
 
 impl Opcode for LDFVx {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         let vx = processor.memory.registers.v[self.reg as usize];
+        // There are only glyphs for the hex digits
         if vx > 0xF {
-            panic!(
-                "Invalid Opcode: Value in Register V{} exceeds 0xF.",
-                self.reg
-            );
+            return Err(self.invalid());
         }
 
-        processor.memory.registers.i = (6 * vx) as u16;
+        let memory = &mut processor.memory;
+        memory.registers.i = memory.resolve(memory.font_addr as usize + GLYPH_LEN * vx as usize)?;
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -713,17 +879,19 @@ impl fmt::Display for LDFVx {
 }
 
 impl Opcode for LDBVx {
-    fn execute(&self, processor: &mut Processor) {
-        //@TODO: Validate since this has nothing to do with real BCD here.
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
         // Store BCD representation of Vx in I, I+1 and I+2 (100s, 10s, 1s)
         let vx = processor.memory.registers.v[self.reg as usize];
         let hundreds: u8 = vx / 100;
-        let tens: u8 = (vx - hundreds) / 10;
-        let ones: u8 = vx - hundreds - tens;
-        let i = processor.memory.registers.i;
-        processor.memory.ram[i as usize] = hundreds;
-        processor.memory.ram[(i + 1) as usize] = tens;
-        processor.memory.ram[(i + 2) as usize] = ones;
+        let tens: u8 = vx / 10 % 10;
+        let ones: u8 = vx % 10;
+        let i = processor.memory.registers.i as usize;
+        for (offset, digit) in [hundreds, tens, ones].iter().enumerate() {
+            let addr = processor.memory.resolve(i + offset)?;
+            processor.memory.write_u8(addr, *digit)?;
+        }
+        processor.invalidate_decode_cache(processor.memory.wrap(i), 3);
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -738,8 +906,20 @@ impl fmt::Display for LDBVx {
 }
 
 impl Opcode for LDIVx {
-    fn execute(&self, _processor: &mut Processor) {
-        unimplemented!()
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let i = processor.memory.registers.i as usize;
+        for x in 0..=self.reg as usize {
+            let addr = processor.memory.resolve(i + x)?;
+            processor
+                .memory
+                .write_u8(addr, processor.memory.registers.v[x])?;
+        }
+        processor.invalidate_decode_cache(processor.memory.wrap(i), self.reg as usize + 1);
+        if processor.instruction_set.load_store_increments_i() {
+            let memory = &mut processor.memory;
+            memory.registers.i = memory.register_value(i + self.reg as usize + 1)?;
+        }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -749,21 +929,22 @@ impl Opcode for LDIVx {
 
 impl fmt::Display for LDIVx {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "TODO")
+        write!(f, "LD I, V{:X}", self.reg)
     }
 }
 
 impl Opcode for LDVxI {
-    fn execute(&self, processor: &mut Processor) {
-        let i = processor.memory.registers.i;
-        for x in 0..=self.reg {
-            //dbg!((i + x as u16) as usize);
-            //dbg!(processor.memory.ram[(i + x as u16) as usize]);
-            processor.memory.registers.v[x as usize] =
-                processor.memory.ram[(i + x as u16) as usize];
-            //dbg!(processor.memory.registers.v[x as usize]);
-            //dbg!(x);
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let i = processor.memory.registers.i as usize;
+        for x in 0..=self.reg as usize {
+            let addr = processor.memory.resolve(i + x)?;
+            processor.memory.registers.v[x] = processor.memory.read_u8(addr)?;
+        }
+        if processor.instruction_set.load_store_increments_i() {
+            let memory = &mut processor.memory;
+            memory.registers.i = memory.register_value(i + self.reg as usize + 1)?;
         }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -778,60 +959,103 @@ impl fmt::Display for LDVxI {
 }
 
 impl Opcode for SKPKBRDVx {
-    fn execute(&self, _processor: &mut Processor) {}
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let key = processor.memory.registers.v[self.reg as usize] & 0xF;
+        if processor.keyboard.keys[key as usize] {
+            processor.skip_instruction()?;
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xE << 4 | self.reg, 0x9E)
+    }
 }
 
 impl fmt::Display for SKPKBRDVx {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "TODO")
+        write!(f, "SKP V{:X}", self.reg)
     }
 }
 
 impl Opcode for SKNPBRDVx {
-    fn execute(&self, _processor: &mut Processor) {}
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let key = processor.memory.registers.v[self.reg as usize] & 0xF;
+        if !processor.keyboard.keys[key as usize] {
+            processor.skip_instruction()?;
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xE << 4 | self.reg, 0xA1)
+    }
 }
 
 impl fmt::Display for SKNPBRDVx {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "TODO")
+        write!(f, "SKNP V{:X}", self.reg)
     }
 }
 
 impl DRW {
-    fn ld_sprite(&self, processor: &mut Processor) -> Vec<bool> {
-        let i = processor.memory.registers.i as usize;
-        let mut vec: Vec<bool> = Vec::new();
-
-        for byte in &processor.memory.ram[i..i + self.size as usize] {
-            for x in 0..8 {
-                // Note: The inversion here is necessary, because the highest bit is the first one
-                vec.push((byte & (1 << (7 - x))) != 0);
-            }
+    // SUPER-CHIP draws a 16x16 sprite (two bytes per row) when the size is 0
+    fn dimensions(&self, processor: &Processor) -> (u8, u8) {
+        if self.size == 0 && processor.instruction_set.big_sprites() {
+            (16, 16)
+        } else {
+            (8, self.size)
         }
+    }
+
+    // Loads the rows of the sprite at I + offset, aligned to the left like the rows of the display
+    fn ld_sprite(&self, processor: &Processor, offset: usize) -> Result<Vec<u128>, MemoryError> {
+        let i = processor.memory.registers.i as usize + offset;
+        let (width, height) = self.dimensions(processor);
+        let bytes_per_row = (width / 8) as usize;
+        let memory = &processor.memory;
 
-        vec
+        (0..height as usize)
+            .map(|y| {
+                (0..bytes_per_row).try_fold(0u128, |row, byte| {
+                    // Like the other accesses through I, sprites may wrap around, see Addressing
+                    let addr = memory.resolve(i + y * bytes_per_row + byte)?;
+                    Ok(row | (memory.read_u8(addr)? as u128) << (120 - byte * 8))
+                })
+            })
+            .collect()
     }
 }
 
 impl Opcode for DRW {
-    fn execute(&self, processor: &mut Processor) {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        // The starting position wraps around, see SpriteEdges for the rest of the sprite
+        let vx =
+            processor.memory.registers.v[self.reg_x as usize] as usize % processor.display.width();
+        let vy =
+            processor.memory.registers.v[self.reg_y as usize] as usize % processor.display.height();
+        // Only after reading the position, which may be in VF itself
         processor.memory.registers.v[0xF] = 0;
-        let vx = processor.memory.registers.v[self.reg_x as usize] as u16;
-        let vy = processor.memory.registers.v[self.reg_y as usize] as u16;
-        let vec_sprite = self.ld_sprite(processor);
-
-        for y in 0..self.size {
-            for x in 0..8 {
-                if processor.display.screen[((vy + y as u16) * 64 + vx + x as u16) as usize]
-                    != vec_sprite[(y * 8 + x) as usize]
-                {
+        let (width, height) = self.dimensions(processor);
+        let sprite_len = width as usize / 8 * height as usize;
+
+        // XO-CHIP: When multiple planes are selected, the sprite data of each plane follows the previous one
+        let mut offset = 0;
+        for plane in [1u8, 2u8].iter() {
+            if processor.display.planes & plane == 0 {
+                continue;
+            }
+
+            let rows = self.ld_sprite(processor, offset)?;
+            offset += sprite_len;
+
+            for (y, row) in rows.into_iter().enumerate() {
+                if processor.display.draw_row(vx, vy + y, row, *plane) {
                     processor.memory.registers.v[0xF] = 1;
                 }
-
-                processor.display.screen[((vy + y as u16) * 64 + vx + x as u16) as usize] =
-                    vec_sprite[(y * 8 + x) as usize];
             }
         }
+        Ok(())
     }
 
     fn assemble(&self) -> (u8, u8) {
@@ -843,26 +1067,363 @@ impl fmt::Display for DRW {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "DRW V{:X}, V{:X}, {:X}",
+            "DRW V{:X}, V{:X}, {:#X}",
             self.reg_x, self.reg_y, self.size
         )
     }
 }
 
 impl Opcode for RNDVxByte {
-    fn execute(&self, processor: &mut Processor) {
-        // We need as u8 here, because otherwise rust says 256 > 1 Byte, even though the border is exclusive
-        // that way a u16 or larger is generated (but still in the range of 0..255
-        let rnd: u8 = rand::thread_rng().gen_range(0, 256) as u8; // [0, 255]
-        dbg!(rnd);
-        dbg!(self.byte);
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let rnd = processor.rng.next_byte(&processor.memory);
         processor.memory.registers.v[self.reg as usize] = rnd & self.byte;
-        dbg!(processor.memory.registers.v[self.reg as usize]);
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xC << 4 | self.reg, self.byte)
     }
 }
 
 impl fmt::Display for RNDVxByte {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "RND V{:X}, {:X}", self.reg, self.byte)
+        write!(f, "RND V{:X}, {:#X}", self.reg, self.byte)
+    }
+}
+
+impl Opcode for SCD {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.display.scroll_down(self.rows as usize);
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0x0, 0xC0 | self.rows)
+    }
+}
+
+impl fmt::Display for SCD {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SCD {:#X}", self.rows)
+    }
+}
+
+impl Opcode for SCR {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.display.scroll_right(4);
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0x0, 0xFB)
+    }
+}
+
+impl fmt::Display for SCR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SCR")
+    }
+}
+
+impl Opcode for SCL {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.display.scroll_left(4);
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0x0, 0xFC)
+    }
+}
+
+impl fmt::Display for SCL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SCL")
+    }
+}
+
+impl Opcode for EXIT {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.halted = true;
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0x0, 0xFD)
+    }
+}
+
+impl fmt::Display for EXIT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EXIT")
+    }
+}
+
+impl Opcode for LOW {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.display.set_hires(false);
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0x0, 0xFE)
+    }
+}
+
+impl fmt::Display for LOW {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LOW")
+    }
+}
+
+impl Opcode for HIGH {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.display.set_hires(true);
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0x0, 0xFF)
+    }
+}
+
+impl fmt::Display for HIGH {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HIGH")
+    }
+}
+
+impl Opcode for LDHFVx {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let vx = processor.memory.registers.v[self.reg as usize];
+        // There are only glyphs for the hex digits
+        if vx > 0xF {
+            return Err(self.invalid());
+        }
+
+        let memory = &mut processor.memory;
+        memory.registers.i =
+            memory.resolve(memory.big_font_addr() + BIG_GLYPH_LEN * vx as usize)?;
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF << 4 | self.reg, 0x30)
+    }
+}
+
+impl fmt::Display for LDHFVx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LD HF, V{:X}", self.reg)
+    }
+}
+
+impl Opcode for LDRVx {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let flags = processor.instruction_set.rpl_flags();
+        if self.reg as usize >= flags {
+            return Err(self.invalid());
+        }
+
+        let registers = &mut processor.memory.registers;
+        for x in 0..=self.reg as usize {
+            registers.rpl[x] = registers.v[x];
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF << 4 | self.reg, 0x75)
+    }
+}
+
+impl fmt::Display for LDRVx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LD R, V{:X}", self.reg)
+    }
+}
+
+impl Opcode for LDVxR {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let flags = processor.instruction_set.rpl_flags();
+        if self.reg as usize >= flags {
+            return Err(self.invalid());
+        }
+
+        let registers = &mut processor.memory.registers;
+        for x in 0..=self.reg as usize {
+            registers.v[x] = registers.rpl[x];
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF << 4 | self.reg, 0x85)
+    }
+}
+
+impl fmt::Display for LDVxR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LD V{:X}, R", self.reg)
+    }
+}
+
+impl LDILong {
+    pub fn new(high: u8, low: u8) -> LDILong {
+        LDILong {
+            address: (((high as u16) << 8) | low as u16),
+        }
+    }
+}
+
+impl Opcode for LDILong {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.memory.registers.i = self.address;
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF0, 0x00)
+    }
+
+    fn size(&self) -> u16 {
+        4
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        vec![0xF0, 0x00, (self.address >> 8) as u8, self.address as u8]
+    }
+
+    fn instruction(&self) -> Instruction {
+        Instruction::LDILong {
+            address: self.address,
+        }
+    }
+}
+
+impl fmt::Display for LDILong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LDIL {:#X}", self.address)
+    }
+}
+
+// Iterates from reg_a to reg_b, both inclusive. If reg_a > reg_b the registers are visited in reverse order
+fn register_range(reg_a: u8, reg_b: u8) -> Vec<usize> {
+    if reg_a <= reg_b {
+        (reg_a as usize..=reg_b as usize).collect()
+    } else {
+        (reg_b as usize..=reg_a as usize).rev().collect()
+    }
+}
+
+impl Opcode for SAVEVxVy {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let i = processor.memory.registers.i as usize;
+        let range = register_range(self.reg_a, self.reg_b);
+        for (offset, reg) in range.iter().enumerate() {
+            let addr = processor.memory.resolve(i + offset)?;
+            processor
+                .memory
+                .write_u8(addr, processor.memory.registers.v[*reg])?;
+        }
+        processor.invalidate_decode_cache(processor.memory.wrap(i), range.len());
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (5 << 4 | self.reg_a, self.reg_b << 4 | 2)
+    }
+}
+
+impl fmt::Display for SAVEVxVy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SAVE V{:X}, V{:X}", self.reg_a, self.reg_b)
+    }
+}
+
+impl Opcode for LOADVxVy {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let i = processor.memory.registers.i as usize;
+        for (offset, reg) in register_range(self.reg_a, self.reg_b)
+            .into_iter()
+            .enumerate()
+        {
+            let addr = processor.memory.resolve(i + offset)?;
+            processor.memory.registers.v[reg] = processor.memory.read_u8(addr)?;
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (5 << 4 | self.reg_a, self.reg_b << 4 | 3)
+    }
+}
+
+impl fmt::Display for LOADVxVy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LOAD V{:X}, V{:X}", self.reg_a, self.reg_b)
+    }
+}
+
+impl Opcode for PLANE {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        // There are only two bitplanes
+        if self.planes > 3 {
+            return Err(self.invalid());
+        }
+
+        processor.display.planes = self.planes;
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF << 4 | self.planes, 0x01)
     }
 }
+
+impl fmt::Display for PLANE {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PLANE {:#X}", self.planes)
+    }
+}
+
+impl Opcode for AUDIO {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        let i = processor.memory.registers.i as usize;
+        for offset in 0..processor.memory.registers.pattern.len() {
+            let addr = processor.memory.resolve(i + offset)?;
+            processor.memory.registers.pattern[offset] = processor.memory.read_u8(addr)?;
+        }
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF0, 0x02)
+    }
+}
+
+impl fmt::Display for AUDIO {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AUDIO")
+    }
+}
+
+impl Opcode for PITCHVx {
+    fn execute(&self, processor: &mut Processor) -> Result<(), Fault> {
+        processor.memory.registers.pitch = processor.memory.registers.v[self.reg as usize];
+        Ok(())
+    }
+
+    fn assemble(&self) -> (u8, u8) {
+        (0xF << 4 | self.reg, 0x3A)
+    }
+}
+
+impl fmt::Display for PITCHVx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PITCH V{:X}", self.reg)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,925 @@
+use super::*;
+use crate::internals::display::SpriteEdges;
+use crate::internals::memory::Addressing;
+use crate::internals::random::FixedSequence;
+use crate::internals::test_support::START;
+use crate::internals::variants::Variant;
+
+fn processor(variant: Variant) -> Processor {
+    Processor::with_program(variant, &[])
+}
+
+fn chip8() -> Processor {
+    processor(Variant::Chip8)
+}
+
+fn schip() -> Processor {
+    processor(Variant::SuperChip)
+}
+
+fn xochip() -> Processor {
+    processor(Variant::XoChip)
+}
+
+// Loads the program and executes as many instructions as it has words
+fn execute(processor: &mut Processor, program: &[u16]) {
+    processor.load_program(program);
+    for _ in program {
+        processor.tick().unwrap();
+    }
+}
+
+fn v(processor: &Processor, reg: usize) -> u8 {
+    processor.memory.registers.v[reg]
+}
+
+// Decodes the word like Processor::tick does and checks the struct it is decoded to, its
+// encoding and its text
+macro_rules! check_opcode {
+    ($processor:expr, $word:expr, $opcode:ty, $text:expr) => {{
+        let [high, low] = u16::to_be_bytes($word);
+        let op = $processor.decode_opcode((high, low));
+        assert!(op.is::<$opcode>(), "{:#06X} decoded to {:?}", $word, op);
+        assert_eq!(op.assemble(), (high, low), "{}", $text);
+        assert_eq!(op.to_string(), $text);
+    }};
+}
+
+#[test]
+fn chip8_encoding() {
+    let mut p = chip8();
+    check_opcode!(p, 0x00E0, CLS, "CLS");
+    check_opcode!(p, 0x00EE, RET, "RET");
+    check_opcode!(p, 0x1234, JMP, "JMP 0x234");
+    check_opcode!(p, 0x2FFE, CALL, "CALL 0xFFE");
+    check_opcode!(p, 0x3A42, SEVxByte, "SE VA, 0x42");
+    check_opcode!(p, 0x4B00, SNEVxByte, "SNE VB, 0x0");
+    check_opcode!(p, 0x5120, SEVxVy, "SE V1, V2");
+    check_opcode!(p, 0x6FFF, LDVxByte, "LD VF, 0xFF");
+    check_opcode!(p, 0x7C01, ADDVxByte, "ADD VC, 0x1");
+    check_opcode!(p, 0x8120, LDVxVy, "LD V1, V2");
+    check_opcode!(p, 0x8121, ORVxVy, "OR V1, V2");
+    check_opcode!(p, 0x8122, ANDVxVy, "AND V1, V2");
+    check_opcode!(p, 0x8123, XORVxVy, "XOR V1, V2");
+    check_opcode!(p, 0x8F04, ADDVxVy, "ADD VF, V0");
+    check_opcode!(p, 0x8125, SUBVxVy, "SUB V1, V2");
+    check_opcode!(p, 0x8126, SHRVxVy, "SHR V1, V2");
+    check_opcode!(p, 0x8127, SUBNVxVy, "SUBN V1, V2");
+    check_opcode!(p, 0x812E, SHLVxVy, "SHL V1, V2");
+    check_opcode!(p, 0x9120, SNEVxVy, "SNE V1, V2");
+    check_opcode!(p, 0xA123, LDIAddr, "LDI 0x123");
+    check_opcode!(p, 0xB300, JPV0Offset, "JP V0, 0x300");
+    check_opcode!(p, 0xC30F, RNDVxByte, "RND V3, 0xF");
+    check_opcode!(p, 0xD125, DRW, "DRW V1, V2, 0x5");
+    check_opcode!(p, 0xE49E, SKPKBRDVx, "SKP V4");
+    check_opcode!(p, 0xE4A1, SKNPBRDVx, "SKNP V4");
+    check_opcode!(p, 0xF507, LDVxDT, "LD V5, DT");
+    check_opcode!(p, 0xF50A, LDVxK, "LD V5, K");
+    check_opcode!(p, 0xF515, LDDTVx, "LD DT, V5");
+    check_opcode!(p, 0xF518, LDSTVx, "LD ST, V5");
+    check_opcode!(p, 0xF51E, ADDIVx, "ADD I, V5");
+    check_opcode!(p, 0xF529, LDFVx, "LD F, V5");
+    check_opcode!(p, 0xF533, LDBVx, "LD B, V5");
+    check_opcode!(p, 0xF555, LDIVx, "LD I, V5");
+    check_opcode!(p, 0xF565, LDVxI, "LD V5, I");
+}
+
+#[test]
+fn invalid_encoding() {
+    let mut p = chip8();
+    // Neither the SUPER-CHIP nor the XO-CHIP extensions exist on the original
+    for word in [
+        0x00FF, 0x00C1, 0x5122, 0x812F, 0x9121, 0xE100, 0xF030, 0xF001, 0xFFFF,
+    ] {
+        check_opcode!(p, word, InvalidOpcode, "INVALID");
+    }
+}
+
+#[test]
+fn schip_encoding() {
+    let mut p = schip();
+    check_opcode!(p, 0x00C4, SCD, "SCD 0x4");
+    check_opcode!(p, 0x00FB, SCR, "SCR");
+    check_opcode!(p, 0x00FC, SCL, "SCL");
+    check_opcode!(p, 0x00FD, EXIT, "EXIT");
+    check_opcode!(p, 0x00FE, LOW, "LOW");
+    check_opcode!(p, 0x00FF, HIGH, "HIGH");
+    check_opcode!(p, 0xF330, LDHFVx, "LD HF, V3");
+    check_opcode!(p, 0xF775, LDRVx, "LD R, V7");
+    check_opcode!(p, 0xF785, LDVxR, "LD V7, R");
+    check_opcode!(p, 0x8125, SUBVxVy, "SUB V1, V2");
+}
+
+#[test]
+fn xochip_encoding() {
+    let mut p = xochip();
+    check_opcode!(p, 0x5132, SAVEVxVy, "SAVE V1, V3");
+    check_opcode!(p, 0x5313, LOADVxVy, "LOAD V3, V1");
+    check_opcode!(p, 0xF201, PLANE, "PLANE 0x2");
+    check_opcode!(p, 0xF002, AUDIO, "AUDIO");
+    check_opcode!(p, 0xF43A, PITCHVx, "PITCH V4");
+    check_opcode!(p, 0x00FF, HIGH, "HIGH");
+
+    p.load_program(&[0xF000, 0xABCD]);
+    let op = p.decode_at(START);
+    assert!(op.is::<LDILong>());
+    assert_eq!(op.size(), 4);
+    assert_eq!(op.encode(), vec![0xF0, 0x00, 0xAB, 0xCD]);
+    assert_eq!(op.to_string(), "LDIL 0xABCD");
+}
+
+#[test]
+fn cls() {
+    let mut p = chip8();
+    p.display.draw_row(0, 0, 1 << 127, 1);
+    execute(&mut p, &[0x00E0]);
+    assert_eq!(p.display.pixels().count(), 0);
+}
+
+#[test]
+fn call_and_ret() {
+    let mut p = chip8();
+    p.load_program(&[0x2206, 0x0000, 0x0000, 0x00EE]);
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.pc, 0x206);
+    assert_eq!(p.memory.registers.sp, 0);
+    assert_eq!(p.memory.stack[0], 0x202);
+
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.pc, 0x202);
+    assert_eq!(p.memory.registers.sp, -1);
+}
+
+#[test]
+fn stack_overflow_and_underflow() {
+    // A subroutine calling itself
+    let mut p = chip8();
+    p.load_program(&[0x2200]);
+    for _ in 0..16 {
+        p.tick().unwrap();
+    }
+    assert_eq!(p.tick(), Err(Fault::StackOverflow { depth: 16 }));
+    assert_eq!((p.memory.registers.pc, p.memory.registers.sp), (START, 15));
+    assert_eq!(p.memory.backtrace(), vec![START; 16]);
+
+    let mut p = schip();
+    p.load_program(&[0x2200]);
+    for _ in 0..32 {
+        p.tick().unwrap();
+    }
+    assert!(p.tick().is_err());
+
+    // Like the COSMAC VIP
+    let mut p = chip8();
+    p.memory.stack = vec![0; 12];
+    p.load_program(&[0x2202, 0x2204, 0x00EE]);
+    p.tick().unwrap();
+    p.tick().unwrap();
+    assert_eq!(p.memory.backtrace(), vec![0x202, 0x200]);
+    p.tick().unwrap();
+    p.tick().unwrap();
+    assert_eq!((p.memory.registers.pc, p.memory.registers.sp), (0x202, -1));
+    p.memory.registers.pc = 0x204;
+    assert_eq!(p.tick(), Err(Fault::StackUnderflow));
+    assert_eq!((p.memory.registers.pc, p.memory.registers.sp), (0x204, -1));
+}
+
+#[test]
+fn invalid_instructions() {
+    let invalid = |opcode| Err(Fault::InvalidInstruction { opcode });
+
+    // Errors leave the pc at the instruction
+    let mut p = chip8();
+    p.load_program(&[0xFFFF]);
+    assert_eq!(p.tick(), invalid(0xFFFF));
+    assert_eq!(p.memory.registers.pc, START);
+
+    // Only the hex digits have glyphs
+    let mut p = schip();
+    p.memory.registers.v[3] = 0x10;
+    p.load_program(&[0xF329]);
+    assert_eq!(p.tick(), invalid(0xF329));
+    p.load_program(&[0xF330]);
+    assert_eq!(p.tick(), invalid(0xF330));
+
+    // SUPER-CHIP only has 8 RPL flags
+    p.load_program(&[0xF875]);
+    assert_eq!(p.tick(), invalid(0xF875));
+    p.load_program(&[0xF885]);
+    assert_eq!(p.tick(), invalid(0xF885));
+    assert_eq!(p.memory.registers.pc, START);
+
+    // Two bitplanes can't be combined to more than 3
+    let mut p = xochip();
+    p.load_program(&[0xF401]);
+    assert_eq!(p.tick(), invalid(0xF401));
+    assert_eq!(p.display.planes, 1);
+}
+
+#[test]
+fn jmp() {
+    let mut p = chip8();
+    execute(&mut p, &[0x1ABC]);
+    assert_eq!(p.memory.registers.pc, 0xABC);
+
+    // Waiting forever is fine
+    execute(&mut p, &[0x1200, 0x1200]);
+    assert_eq!(p.memory.registers.pc, START);
+}
+
+#[test]
+fn jp_v0_offset() {
+    let mut p = chip8();
+    p.memory.registers.v[0] = 0x10;
+    p.memory.registers.v[3] = 0x20;
+    execute(&mut p, &[0xB300]);
+    assert_eq!(p.memory.registers.pc, 0x310);
+
+    // SUPER-CHIP's Bxnn adds Vx instead
+    let mut p = schip();
+    p.memory.registers.v[0] = 0x10;
+    p.memory.registers.v[3] = 0x20;
+    execute(&mut p, &[0xB300]);
+    assert_eq!(p.memory.registers.pc, 0x320);
+}
+
+#[test]
+fn skips() {
+    let cases = [
+        // (instruction, whether it skips with V1 = 0x42 and V2 = 0x42, V3 = 0x00)
+        (0x3142, true),
+        (0x3143, false),
+        (0x4142, false),
+        (0x4143, true),
+        (0x5120, true),
+        (0x5130, false),
+        (0x9120, false),
+        (0x9130, true),
+    ];
+
+    for &(word, skips) in cases.iter() {
+        let mut p = chip8();
+        p.memory.registers.v[1] = 0x42;
+        p.memory.registers.v[2] = 0x42;
+        p.load_program(&[word]);
+        p.tick().unwrap();
+        let expected = if skips { START + 4 } else { START + 2 };
+        assert_eq!(p.memory.registers.pc, expected, "{:#06X}", word);
+    }
+}
+
+#[test]
+fn skip_over_long_instruction() {
+    let mut p = xochip();
+    p.load_program(&[0x3000, 0xF000, 0x1234]);
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.pc, START + 6);
+}
+
+#[test]
+fn ld_and_add_byte() {
+    let mut p = chip8();
+    execute(&mut p, &[0x6AFE, 0x7A03]);
+    // The carry is lost and VF stays untouched
+    assert_eq!(v(&p, 0xA), 0x01);
+    assert_eq!(v(&p, 0xF), 0);
+}
+
+#[test]
+fn ld_vx_vy() {
+    let mut p = chip8();
+    p.memory.registers.v[2] = 0x99;
+    execute(&mut p, &[0x8120]);
+    assert_eq!(v(&p, 1), 0x99);
+    assert_eq!(v(&p, 2), 0x99);
+}
+
+#[test]
+fn logic() {
+    for &(word, result) in [(0x8121, 0b1110), (0x8122, 0b1000), (0x8123, 0b0110)].iter() {
+        for &(variant, vf) in [(Variant::Chip8, 0), (Variant::SuperChip, 7)].iter() {
+            let mut p = processor(variant);
+            p.memory.registers.v[1] = 0b1100;
+            p.memory.registers.v[2] = 0b1010;
+            p.memory.registers.v[0xF] = 7;
+            execute(&mut p, &[word]);
+            assert_eq!(v(&p, 1), result, "{:#06X}", word);
+            assert_eq!(v(&p, 0xF), vf, "{:#06X} on {:?}", word, variant);
+        }
+    }
+}
+
+#[test]
+fn add_vx_vy() {
+    let mut p = chip8();
+    p.memory.registers.v[1] = 0xFF;
+    p.memory.registers.v[2] = 0x02;
+    execute(&mut p, &[0x8124]);
+    assert_eq!((v(&p, 1), v(&p, 0xF)), (0x01, 1));
+
+    p.memory.registers.v[1] = 0x10;
+    execute(&mut p, &[0x8124]);
+    assert_eq!((v(&p, 1), v(&p, 0xF)), (0x12, 0));
+}
+
+#[test]
+fn sub_and_subn() {
+    let cases = [
+        // (instruction, V1, V2, result, VF)
+        (0x8125, 5, 3, 2, 1),
+        (0x8125, 3, 3, 0, 1),
+        (0x8125, 3, 5, 0xFE, 0),
+        (0x8127, 3, 5, 2, 1),
+        (0x8127, 5, 5, 0, 1),
+        (0x8127, 5, 3, 0xFE, 0),
+    ];
+
+    for &(word, v1, v2, result, vf) in cases.iter() {
+        let mut p = chip8();
+        p.memory.registers.v[1] = v1;
+        p.memory.registers.v[2] = v2;
+        execute(&mut p, &[word]);
+        assert_eq!((v(&p, 1), v(&p, 0xF)), (result, vf), "{:#06X}", word);
+    }
+}
+
+#[test]
+fn shifts() {
+    let cases = [
+        // (variant, instruction, result, VF) with V1 = 0x81 and V2 = 0x02
+        (Variant::Chip8, 0x8126, 0x01, 0),
+        (Variant::Chip8, 0x812E, 0x04, 0),
+        (Variant::SuperChip, 0x8126, 0x40, 1),
+        (Variant::SuperChip, 0x812E, 0x02, 1),
+        (Variant::XoChip, 0x8126, 0x01, 0),
+    ];
+
+    for &(variant, word, result, vf) in cases.iter() {
+        let mut p = processor(variant);
+        p.memory.registers.v[1] = 0x81;
+        p.memory.registers.v[2] = 0x02;
+        execute(&mut p, &[word]);
+        assert_eq!(
+            (v(&p, 1), v(&p, 0xF)),
+            (result, vf),
+            "{:#06X} on {:?}",
+            word,
+            variant
+        );
+    }
+}
+
+#[test]
+fn vf_as_operand() {
+    // The flag is written after the result, so it wins
+    let cases = [
+        // (instruction, VF, V1, VF afterwards)
+        (0x8F14, 0xFF, 0x01, 1),
+        (0x8F14, 0x01, 0x01, 0),
+        (0x8F15, 0x01, 0x02, 0),
+        (0x8F17, 0x01, 0x02, 1),
+        (0x8F16, 0x00, 0x01, 1),
+        (0x8F1E, 0x00, 0x40, 0),
+        (0x81F4, 0x01, 0xFF, 1),
+    ];
+
+    for &(word, vf, v1, expected) in cases.iter() {
+        let mut p = chip8();
+        p.memory.registers.v[0xF] = vf;
+        p.memory.registers.v[1] = v1;
+        execute(&mut p, &[word]);
+        assert_eq!(v(&p, 0xF), expected, "{:#06X}", word);
+    }
+}
+
+#[test]
+fn timers() {
+    let mut p = chip8();
+    p.memory.registers.v[1] = 30;
+    execute(&mut p, &[0xF115, 0xF118, 0xF207]);
+    assert_eq!(p.memory.registers.dt, 30);
+    assert_eq!(p.memory.registers.st, 30);
+    // Reading the delay timer doesn't count it down, only tick_timers does
+    assert_eq!(v(&p, 2), 30);
+
+    p.tick_timers();
+    execute(&mut p, &[0xF207]);
+    assert_eq!(v(&p, 2), 29);
+    assert_eq!(p.memory.registers.st, 29);
+}
+
+#[test]
+fn wait_for_key() {
+    let mut p = chip8();
+    p.load_program(&[0xF30A]);
+    p.tick().unwrap();
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.pc, START);
+
+    p.keyboard.keys[0xB] = true;
+    p.tick().unwrap();
+    assert_eq!(p.memory.registers.pc, START + 2);
+    assert_eq!(v(&p, 3), 0xB);
+}
+
+#[test]
+fn key_skips() {
+    let mut p = chip8();
+    p.keyboard.keys[5] = true;
+    p.memory.registers.v[1] = 5;
+    p.memory.registers.v[2] = 6;
+
+    for &(word, skips) in [
+        (0xE19E, true),
+        (0xE29E, false),
+        (0xE1A1, false),
+        (0xE2A1, true),
+    ]
+    .iter()
+    {
+        p.load_program(&[word]);
+        p.tick().unwrap();
+        let expected = if skips { START + 4 } else { START + 2 };
+        assert_eq!(p.memory.registers.pc, expected, "{:#06X}", word);
+    }
+}
+
+#[test]
+fn i_register() {
+    let mut p = chip8();
+    p.memory.registers.v[1] = 0x10;
+    execute(&mut p, &[0xA300, 0xF11E]);
+    assert_eq!(p.memory.registers.i, 0x310);
+
+    p.memory.registers.v[1] = 0xA;
+    execute(&mut p, &[0xF129]);
+    assert_eq!(p.memory.registers.i, 0x50 + 5 * 0xA);
+
+    let mut p = schip();
+    p.memory.registers.v[1] = 2;
+    execute(&mut p, &[0xF130]);
+    assert_eq!(p.memory.registers.i, 0xA0 + 20);
+}
+
+#[test]
+fn bcd() {
+    for &(value, digits) in [
+        (254, [2, 5, 4]),
+        (100, [1, 0, 0]),
+        (7, [0, 0, 7]),
+        (0, [0, 0, 0]),
+    ]
+    .iter()
+    {
+        let mut p = chip8();
+        p.memory.registers.v[4] = value;
+        execute(&mut p, &[0xA300, 0xF433]);
+        assert_eq!(&p.memory.ram[0x300..0x303], &digits, "{}", value);
+        assert_eq!(p.memory.registers.i, 0x300);
+    }
+}
+
+#[test]
+fn store_and_load_registers() {
+    // (variant, I after storing V0 to V2, I after loading V0 and V1)
+    let cases = [
+        (Variant::Chip8, 0x303, 0x302),
+        (Variant::SuperChip, 0x300, 0x300),
+    ];
+    for &(variant, stored, loaded) in cases.iter() {
+        let mut p = processor(variant);
+        p.memory.registers.v[..4].copy_from_slice(&[1, 2, 3, 4]);
+        execute(&mut p, &[0xA300, 0xF255]);
+        assert_eq!(&p.memory.ram[0x300..0x304], &[1, 2, 3, 0]);
+        assert_eq!(p.memory.registers.i, stored, "{:?}", variant);
+
+        p.memory.ram[0x300..0x304].copy_from_slice(&[5, 6, 7, 8]);
+        execute(&mut p, &[0xA300, 0xF165]);
+        assert_eq!(&p.memory.registers.v[..4], &[5, 6, 3, 4]);
+        assert_eq!(p.memory.registers.i, loaded, "{:?}", variant);
+    }
+}
+
+#[test]
+fn store_invalidates_decode_cache() {
+    // Overwrites the following instruction (CLS) with LD V0, 0x42
+    let mut p = chip8();
+    p.memory.registers.v[0] = 0x60;
+    p.memory.registers.v[1] = 0x42;
+    execute(&mut p, &[0xA204, 0xF155, 0x00E0]);
+    assert_eq!(v(&p, 0), 0x42);
+}
+
+#[test]
+fn rnd() {
+    let mut p = chip8();
+    p.rng = Box::new(FixedSequence::new(vec![0xAB, 0xFF]));
+    execute(&mut p, &[0xC10F, 0xC2F0, 0xC3FF]);
+    assert_eq!((v(&p, 1), v(&p, 2), v(&p, 3)), (0x0B, 0xF0, 0xAB));
+}
+
+#[test]
+fn drw() {
+    let mut p = chip8();
+    p.memory.ram[0x300] = 0b1100_0000;
+    p.memory.registers.v[1] = 10;
+    p.memory.registers.v[2] = 20;
+    execute(&mut p, &[0xA300, 0xD121]);
+    assert_eq!(
+        p.display.pixels().collect::<Vec<_>>(),
+        vec![(10, 20, 1), (11, 20, 1)]
+    );
+    assert_eq!(v(&p, 0xF), 0);
+
+    // Drawing it again erases it and reports the collision
+    execute(&mut p, &[0xA300, 0xD121]);
+    assert_eq!(p.display.pixels().count(), 0);
+    assert_eq!(v(&p, 0xF), 1);
+}
+
+#[test]
+fn drw_at_vf() {
+    // The position is read before VF is reset
+    let mut p = chip8();
+    p.memory.ram[0x300] = 0b1000_0000;
+    p.memory.registers.v[0xF] = 10;
+    p.memory.registers.v[1] = 20;
+    execute(&mut p, &[0xA300, 0xDF11]);
+    assert_eq!(p.display.pixels().collect::<Vec<_>>(), vec![(10, 20, 1)]);
+    assert_eq!(v(&p, 0xF), 0);
+
+    let mut p = chip8();
+    p.memory.ram[0x300] = 0b1000_0000;
+    p.memory.registers.v[1] = 20;
+    p.memory.registers.v[0xF] = 10;
+    execute(&mut p, &[0xA300, 0xD1F1]);
+    assert_eq!(p.display.pixels().collect::<Vec<_>>(), vec![(20, 10, 1)]);
+    assert_eq!(v(&p, 0xF), 0);
+
+    // And it is the collision flag afterwards
+    p.memory.registers.v[0xF] = 10;
+    execute(&mut p, &[0xA300, 0xD1F1]);
+    assert_eq!(p.display.pixels().count(), 0);
+    assert_eq!(v(&p, 0xF), 1);
+}
+
+#[test]
+fn drw_edges() {
+    let mut p = chip8();
+    p.memory.ram[0x300] = 0b1100_0000;
+    p.memory.ram[0x301] = 0b1100_0000;
+
+    // The position wraps around, the rest of the sprite is clipped
+    p.memory.registers.v[1] = 64 + 63;
+    p.memory.registers.v[2] = 31;
+    execute(&mut p, &[0xA300, 0xD122]);
+    assert_eq!(p.display.pixels().collect::<Vec<_>>(), vec![(63, 31, 1)]);
+
+    let mut p = chip8();
+    p.display.sprite_edges = SpriteEdges::Wrap;
+    p.memory.ram[0x300] = 0b1100_0000;
+    p.memory.ram[0x301] = 0b1100_0000;
+    p.memory.registers.v[1] = 63;
+    p.memory.registers.v[2] = 31;
+    execute(&mut p, &[0xA300, 0xD122]);
+    assert_eq!(
+        p.display.pixels().collect::<Vec<_>>(),
+        vec![(0, 0, 1), (63, 0, 1), (0, 31, 1), (63, 31, 1)]
+    );
+}
+
+#[test]
+fn drw_big_sprite() {
+    let mut p = schip();
+    p.memory.ram[0x300..0x320].copy_from_slice(&[0xFF; 32]);
+    execute(&mut p, &[0x00FF, 0xA300, 0xD120]);
+    assert!(p.display.hires);
+    assert_eq!(p.display.pixels().count(), 16 * 16);
+
+    // The original draws nothing for a size of 0
+    let mut p = chip8();
+    p.memory.ram[0x300..0x320].copy_from_slice(&[0xFF; 32]);
+    execute(&mut p, &[0xA300, 0xD120]);
+    assert_eq!(p.display.pixels().count(), 0);
+}
+
+#[test]
+fn scrolling() {
+    let mut p = schip();
+    execute(&mut p, &[0x00FF]);
+    p.display.draw_row(8, 8, 1 << 127, 1);
+    execute(&mut p, &[0x00C3]);
+    assert_eq!(p.display.pixels().collect::<Vec<_>>(), vec![(8, 11, 1)]);
+    execute(&mut p, &[0x00FB]);
+    assert_eq!(p.display.pixels().collect::<Vec<_>>(), vec![(12, 11, 1)]);
+    execute(&mut p, &[0x00FC, 0x00FC]);
+    assert_eq!(p.display.pixels().collect::<Vec<_>>(), vec![(4, 11, 1)]);
+
+    execute(&mut p, &[0x00FE]);
+    assert!(!p.display.hires);
+    assert_eq!(p.display.pixels().count(), 0);
+}
+
+#[test]
+fn exit() {
+    let mut p = schip();
+    execute(&mut p, &[0x00FD, 0x6001]);
+    assert!(p.halted);
+    assert_eq!(v(&p, 0), 0);
+}
+
+#[test]
+fn rpl_flags() {
+    let mut p = schip();
+    p.memory.registers.v[..3].copy_from_slice(&[1, 2, 3]);
+    execute(&mut p, &[0xF275, 0x6000, 0x6100, 0xF185]);
+    assert_eq!(&p.memory.registers.rpl[..3], &[1, 2, 3]);
+    assert_eq!(&p.memory.registers.v[..3], &[1, 2, 3]);
+}
+
+#[test]
+fn xochip_registers_ranges() {
+    let mut p = xochip();
+    p.memory.registers.v[1..4].copy_from_slice(&[1, 2, 3]);
+    execute(&mut p, &[0xA300, 0x5132, 0x5313]);
+    assert_eq!(&p.memory.ram[0x300..0x303], &[1, 2, 3]);
+    // Loading in reverse order, I isn't changed by either
+    assert_eq!(&p.memory.registers.v[1..4], &[3, 2, 1]);
+    assert_eq!(p.memory.registers.i, 0x300);
+}
+
+#[test]
+fn xochip_planes_and_audio() {
+    let mut p = xochip();
+    p.memory.ram[0x300] = 0x80;
+    p.memory.ram[0x301] = 0x80;
+    execute(&mut p, &[0xF301, 0xA300, 0xD001]);
+    assert_eq!(p.display.pixel(0, 0), 3);
+
+    p.memory.ram[0x300..0x310].copy_from_slice(&[0xAA; 16]);
+    p.memory.registers.v[1] = 100;
+    execute(&mut p, &[0xA300, 0xF002, 0xF13A]);
+    assert_eq!(p.memory.registers.pattern, [0xAA; 16]);
+    assert_eq!(p.memory.registers.pitch, 100);
+}
+
+// The mini-ROMs below check the behaviour the well-known community test programs check for, by
+// drawing the font digit of some register per result and comparing a hash of the final screen
+
+// Draws the font digit of reg (which has to be at most 0xF) into the given column of the top row
+fn draw_digit(reg: u16, column: u16) -> Vec<u16> {
+    vec![0x6D00 | (column * 5), 0xF029 | (reg << 8), 0xDDE5]
+}
+
+// Runs the program until it ends in a jump to itself
+fn run_rom(mut processor: Processor, mut program: Vec<u16>) -> Processor {
+    let end = START + 2 * program.len() as u16;
+    program.push(0x1000 | end);
+    processor.load_program(&program);
+    while processor.memory.registers.pc != end {
+        processor.tick().unwrap();
+    }
+    processor
+}
+
+#[test]
+fn flags_rom() {
+    // Every check leaves its flag in V2, which is drawn afterwards: 1 0 1 0 1 1 1 1
+    let checks: [&[u16]; 8] = [
+        &[0x60FF, 0x6101, 0x8014],
+        &[0x6001, 0x6101, 0x8014],
+        &[0x6005, 0x6103, 0x8015],
+        &[0x6003, 0x6105, 0x8015],
+        &[0x6003, 0x6105, 0x8017],
+        &[0x6103, 0x8016],
+        &[0x6180, 0x801E],
+        &[0x6FFF, 0x6101, 0x8F14],
+    ];
+
+    let mut program = Vec::new();
+    for (column, check) in checks.iter().enumerate() {
+        program.extend_from_slice(check);
+        program.push(0x82F0);
+        program.extend(draw_digit(2, column as u16));
+    }
+
+    let p = run_rom(chip8(), program);
+    assert_eq!(p.display.checksum(), 12477809822347689609);
+}
+
+fn quirks_rom() -> Vec<u16> {
+    let mut program = vec![
+        // VF reset by OR
+        0x6F05, 0x6001, 0x6102, 0x8011, 0x82F0,
+    ];
+    program.extend(draw_digit(2, 0));
+    // Shifting Vy or Vx
+    program.extend_from_slice(&[0x6004, 0x6101, 0x8016]);
+    program.extend(draw_digit(0, 1));
+    // Whether I is incremented by LD [I], V0
+    program.extend_from_slice(&[0xA300, 0x6007, 0xF055, 0xF065]);
+    program.extend(draw_digit(0, 2));
+
+    // Whether B2nn jumps to 2nn + V0 (landing on LD V3, 1) or to 2nn + V2 (landing on LD V3, 2)
+    let target = START + 2 * (program.len() as u16 + 3);
+    program.extend_from_slice(&[0x6000, 0x6204, 0xB000 | target]);
+    program.extend_from_slice(&[0x6301, 0x1000 | (target + 6), 0x6302]);
+    program.extend(draw_digit(3, 3));
+    program
+}
+
+#[test]
+fn chip8_quirks_rom() {
+    // 0 0 0 1
+    let p = run_rom(chip8(), quirks_rom());
+    assert_eq!(p.display.checksum(), 837093531091697799);
+}
+
+#[test]
+fn schip_quirks_rom() {
+    // 5 2 7 2
+    let p = run_rom(schip(), quirks_rom());
+    assert_eq!(p.display.checksum(), 6429873472221612652);
+}
+
+#[test]
+fn keypad_rom() {
+    let mut program = vec![
+        // SKP with the key held down skips LD V2, 0
+        0x6201, 0x6505, 0xE59E, 0x6200,
+    ];
+    program.extend(draw_digit(2, 0));
+    // SKNP with the key up skips LD V2, 0
+    program.extend_from_slice(&[0x6201, 0x6606, 0xE6A1, 0x6200]);
+    program.extend(draw_digit(2, 1));
+    // LD V0, K gets the held key
+    program.push(0xF00A);
+    program.extend(draw_digit(0, 2));
+
+    // 1 1 5
+    let mut p = chip8();
+    p.keyboard.keys[5] = true;
+    let p = run_rom(p, program);
+    assert_eq!(p.display.checksum(), 11451030420993804390);
+}
+
+// Crashes found by the fuzz targets in fuzz/, the memory continues at its beginning
+
+#[test]
+fn jump_past_the_end_of_memory() {
+    let mut p = chip8();
+    p.memory.load(&[0x61, 0x23], 0x0FE);
+    // 0xFFF + 0xFF
+    execute(&mut p, &[0x60FF, 0xBFFF]);
+    p.tick().unwrap();
+    assert_eq!(v(&p, 1), 0x23);
+    assert_eq!(p.memory.registers.pc, 0x100);
+}
+
+#[test]
+fn instructions_at_the_end_of_memory() {
+    // The second byte of the instruction is the first one of the memory
+    let mut p = chip8();
+    p.memory.ram[0xFFF] = 0x60;
+    p.memory.ram[0x000] = 0x42;
+    p.memory.registers.pc = 0xFFF;
+    p.tick().unwrap();
+    assert_eq!((v(&p, 0), p.memory.registers.pc), (0x42, 0x001));
+
+    let mut p = xochip();
+    p.memory.load(&[0x60, 0x42], 0xFFFE);
+    p.memory.registers.pc = 0xFFFE;
+    p.tick().unwrap();
+    assert_eq!((v(&p, 0), p.memory.registers.pc), (0x42, 0x000));
+}
+
+#[test]
+fn i_near_the_end_of_memory() {
+    let mut p = chip8();
+    p.memory.registers.i = 0xFFE;
+    execute(&mut p, &[0x60FE, 0xF033]);
+    assert_eq!(
+        (p.memory.ram[0xFFE], p.memory.ram[0xFFF], p.memory.ram[0]),
+        (2, 5, 4)
+    );
+
+    let mut p = chip8();
+    p.memory.registers.i = 0xFFF;
+    execute(&mut p, &[0x6011, 0x6122, 0xF155]);
+    assert_eq!((p.memory.ram[0xFFF], p.memory.ram[0]), (0x11, 0x22));
+    p.memory.registers.i = 0xFFF;
+    execute(&mut p, &[0x6000, 0x6100, 0xF165]);
+    assert_eq!((v(&p, 0), v(&p, 1)), (0x11, 0x22));
+
+    let mut p = xochip();
+    p.memory.registers.i = 0xFFFF;
+    execute(&mut p, &[0x6011, 0x6122, 0x5012, 0x6102, 0xF11E]);
+    assert_eq!((p.memory.ram[0xFFFF], p.memory.ram[0]), (0x11, 0x22));
+    assert_eq!(p.memory.registers.i, 0x0001);
+
+    let mut p = xochip();
+    p.memory.registers.i = 0xFFF8;
+    execute(&mut p, &[0xF002]);
+    assert_eq!(p.memory.registers.pattern[8..], p.memory.ram[..8]);
+}
+
+#[test]
+fn addressing_modes() {
+    // ADD I, Vx wraps around like the address bus
+    let mut p = chip8();
+    p.memory.registers.i = 0xFFF;
+    execute(&mut p, &[0x6002, 0xF01E]);
+    assert_eq!(p.memory.registers.i, 0x001);
+
+    let mut p = xochip();
+    p.memory.registers.i = 0xFFF;
+    execute(&mut p, &[0x6002, 0xF01E]);
+    assert_eq!(p.memory.registers.i, 0x1001);
+    p.memory.addressing = Addressing::Wrap12;
+    p.memory.registers.i = 0xFFF;
+    execute(&mut p, &[0x6002, 0xF01E]);
+    assert_eq!(p.memory.registers.i, 0x001);
+
+    // Strict: Accesses past the end fail and leave pc pointing to the instruction
+    let out_of_bounds = |addr| Fault::Memory(MemoryError::OutOfBounds { addr });
+    let mut p = chip8();
+    p.memory.addressing = Addressing::Strict;
+    p.memory.registers.i = 0xFFF;
+    p.load_program(&[0xF165]);
+    assert_eq!(p.tick(), Err(out_of_bounds(0x1000)));
+    assert_eq!((p.memory.registers.pc, p.cycles), (START, 0));
+    p.load_program(&[0xD011]);
+    assert_eq!(p.tick(), Ok(()));
+    p.load_program(&[0xD012]);
+    assert_eq!(p.tick(), Err(out_of_bounds(0x1000)));
+
+    // ADD I, Vx doesn't wrap, but I can only be used when it points into the memory again
+    execute(&mut p, &[0x6002, 0xF01E]);
+    assert_eq!(p.memory.registers.i, 0x1001);
+
+    // Neither do jumps and instructions running past the end
+    p.load_program(&[0x1FFE]);
+    p.memory.ram[0xFFE] = 0x00;
+    p.memory.ram[0xFFF] = 0xE0;
+    p.tick().unwrap();
+    p.tick().unwrap();
+    assert_eq!(p.tick(), Err(out_of_bounds(0x1000)));
+    assert_eq!(p.memory.registers.pc, 0x1000);
+    p.memory.registers.pc = 0xFFF;
+    assert_eq!(p.tick(), Err(out_of_bounds(0x1000)));
+    assert_eq!(p.memory.registers.pc, 0xFFF);
+
+    // Extended addresses beyond a 4 KiB memory don't exist
+    let mut p = chip8();
+    p.memory.addressing = Addressing::Extended;
+    p.memory.registers.i = 0xFFF;
+    execute(&mut p, &[0x6002, 0xF01E]);
+    p.load_program(&[0xF065]);
+    assert_eq!(p.tick(), Err(out_of_bounds(0x1001)));
+}
+
+#[test]
+fn strict_addressing_at_the_top_of_64_kib() {
+    let out_of_bounds = |addr| Err(Fault::Memory(MemoryError::OutOfBounds { addr }));
+    let at_the_top = |program: &[u8]| {
+        let mut p = xochip();
+        p.memory.addressing = Addressing::Strict;
+        let start = (0x10000 - program.len()) as u16;
+        p.memory.load(program, start);
+        p.clear_decode_cache();
+        p.memory.registers.pc = start;
+        p
+    };
+
+    // pc can't advance past 0xFFFF, neither by executing nor by skipping the last instruction
+    let mut p = at_the_top(&[0x70, 0x01]);
+    assert_eq!(p.tick(), out_of_bounds(0x10000));
+    assert_eq!((p.memory.registers.pc, v(&p, 0)), (0xFFFE, 0));
+    let mut p = at_the_top(&[0x30, 0x00, 0x00, 0xE0]);
+    assert_eq!(p.tick(), out_of_bounds(0x10000));
+    assert_eq!(p.memory.registers.pc, 0xFFFC);
+
+    // Nor can a CALL return there
+    let mut p = at_the_top(&[0x22, 0x00]);
+    assert_eq!(p.tick(), out_of_bounds(0x10000));
+    assert_eq!((p.memory.registers.pc, p.memory.registers.sp), (0xFFFE, -1));
+
+    // I can't be moved past it
+    let mut p = at_the_top(&[0xF0, 0x1E, 0x00, 0xE0]);
+    p.memory.registers.i = 0xFFFF;
+    p.memory.registers.v[0] = 1;
+    assert_eq!(p.tick(), out_of_bounds(0x10000));
+    assert_eq!(
+        (p.memory.registers.pc, p.memory.registers.i),
+        (0xFFFC, 0xFFFF)
+    );
+    for program in [[0xF0, 0x55, 0x00, 0xE0], [0xF0, 0x65, 0x00, 0xE0]].iter() {
+        let mut p = at_the_top(program);
+        p.memory.registers.i = 0xFFFF;
+        assert_eq!(p.tick(), out_of_bounds(0x10000));
+        assert_eq!(
+            (p.memory.registers.pc, p.memory.registers.i),
+            (0xFFFC, 0xFFFF)
+        );
+    }
+}
@@ -1,22 +1,219 @@
 use crate::internals::display::Display;
+use crate::internals::fault::Fault;
+use crate::internals::font::{Font, BIG_FONT_LEN, FONT_LEN};
+use crate::internals::instruction::Instruction;
 use crate::internals::keyboard::Keyboard;
-use crate::internals::memory::Memory;
+use crate::internals::layout::{self, Layout};
+use crate::internals::memory::{Memory, MemoryError};
 use crate::internals::opcode::*;
+use crate::internals::random::{RandomSource, StdRandom};
+use crate::internals::savestate;
+use crate::internals::timing::{self, Timing};
+use crate::internals::variants::InstructionSet;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+// How many instructions are executed per frame (at 60 frames per second), see Timing::Fixed
+pub const TICKS_PER_FRAME: usize = 10;
 
 pub struct Processor {
     pub memory: Memory,
     pub keyboard: Keyboard,
     pub display: Display,
+    pub instruction_set: Box<dyn InstructionSet>,
+    // Whether the stack, registers and display show up in the RAM
+    pub layout: Layout,
+    // Set by the SUPER-CHIP EXIT instruction, the emulator should stop ticking afterwards
+    pub halted: bool,
+    // Identifies the program in save states, see load_rom
+    pub rom_hash: u64,
+    // The number of executed instructions, used to replay input at the exact same time
+    pub cycles: u64,
+    // What the instructions cost, and how much of the current frame they have used up
+    pub timing: Timing,
+    pub frame_cycles: u32,
+    // The random number source of RNDVxByte and the seed it started with, see reseed
+    pub seed: u64,
+    pub rng: Box<dyn RandomSource>,
+    // The already decoded instruction for every address, so tick doesn't have to decode (and
+    // allocate) every instruction again. Opcodes writing to RAM have to invalidate it.
+    decode_cache: Vec<Option<Rc<dyn Opcode>>>,
 }
 
 impl Processor {
-    pub fn tick(&mut self) {
-        let opcode = self.fetch_opcode();
-        let op: Box<dyn Opcode> = self.decode_opcode(opcode);
+    pub fn new(instruction_set: Box<dyn InstructionSet>) -> Processor {
+        let mut memory = Memory::with_size(instruction_set.memory_size());
+        memory.addressing = instruction_set.addressing();
+        memory.stack = vec![0; instruction_set.stack_depth()];
+        instruction_set
+            .load_fonts(&mut memory, &Font::default())
+            .expect("The font fits into the memory");
+        let seed = rand::random();
+
+        Processor {
+            decode_cache: vec![None; memory.ram.len()],
+            memory,
+            keyboard: Keyboard::default(),
+            display: instruction_set.display(),
+            instruction_set,
+            layout: Layout::Flat,
+            halted: false,
+            rom_hash: 0,
+            cycles: 0,
+            timing: Timing::Fixed,
+            frame_cycles: 0,
+            seed,
+            rng: Box::new(StdRandom::new(seed)),
+        }
+    }
+
+    // Replaces the font and moves it to addr. Has to happen before the program is loaded, as the
+    // memory of the previous font is cleared.
+    pub fn set_font(&mut self, font: &Font, addr: u16) -> Result<(), MemoryError> {
+        // Room for the big font is kept for every variant, so the fonts never move
+        self.memory.range(addr, FONT_LEN + BIG_FONT_LEN)?;
+        let old = self
+            .memory
+            .range(self.memory.font_addr, FONT_LEN + BIG_FONT_LEN)?;
+        self.memory.ram[old].fill(0);
+        self.memory.font_addr = addr;
+        self.instruction_set.load_fonts(&mut self.memory, font)?;
+        self.clear_decode_cache();
+        Ok(())
+    }
+
+    // Restarts the random number source, so the same seed always produces the same numbers
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng.reseed(seed);
+    }
+
+    pub fn load_rom(&mut self, name: &str, loading_point: u16) -> Result<(), io::Error> {
+        let rom = std::fs::read(name)?;
+        self.memory.load(&rom, loading_point);
+        self.rom_hash = savestate::hash(&rom);
+        self.clear_decode_cache();
+        Ok(())
+    }
+
+    // Snapshots the whole machine, see savestate for the format
+    pub fn save_state<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        savestate::write(self, out)
+    }
+
+    // Restores a snapshot made by save_state, which has to be made with the same variant and ROM
+    pub fn load_state<R: Read>(&mut self, input: &mut R) -> io::Result<()> {
+        savestate::read(self, input)
+    }
+
+    // Executes the instruction at pc. When that fails, pc is left pointing to it.
+    pub fn tick(&mut self) -> Result<(), Fault> {
+        if self.halted {
+            return Ok(());
+        }
+
+        // Jumps may lead past the end of the memory, see Addressing for where they end up
+        let pc = self.memory.resolve(self.memory.registers.pc as usize)?;
+        self.memory.registers.pc = pc;
+        let op = self.decode_cached(pc);
+        let size = op.size() as usize;
+        self.memory.resolve(pc as usize + size - 1)?;
         if !op.modified_pc() {
-            self.memory.registers.pc += 2;
+            self.memory.registers.pc = self.memory.register_value(pc as usize + size)?;
+        }
+        let instruction = op.instruction();
+        let cost = match self.timing {
+            Timing::Fixed => 1,
+            Timing::Vip => timing::vip_cycles(&instruction, &self.memory),
+        };
+        let written = match self.layout {
+            Layout::Flat => 0..0,
+            Layout::Vip => instruction.writes_memory(self.memory.registers.i),
+        };
+        if let Err(error) = op.execute(self) {
+            self.memory.registers.pc = pc;
+            return Err(error);
+        }
+        if self.layout == Layout::Vip {
+            layout::load(self, &written);
+            layout::store(self);
+        }
+        self.cycles += 1;
+        self.frame_cycles += cost;
+        // The VIP interpreter waits for the vertical blank after drawing
+        if self.timing == Timing::Vip && matches!(instruction, Instruction::DRW { .. }) {
+            self.frame_cycles = std::cmp::max(self.frame_cycles, self.timing.frame_budget());
+        }
+        Ok(())
+    }
+
+    // Executes as many instructions as fit into a single frame, see Timing. Whatever the last one
+    // took longer than the frame is taken from the next one.
+    pub fn run_frame(&mut self) -> Result<(), Fault> {
+        let budget = self.timing.frame_budget();
+        while self.frame_cycles < budget && !self.halted {
+            self.tick()?;
+        }
+        self.frame_cycles = self.frame_cycles.saturating_sub(budget);
+        Ok(())
+    }
+
+    // For executing one instruction at a time instead of run_frame: Once the instructions have used
+    // up the budget of the frame, it ends it like run_frame does and counts the timers down
+    pub fn end_full_frame(&mut self) {
+        let budget = self.timing.frame_budget();
+        if self.frame_cycles >= budget {
+            self.frame_cycles -= budget;
+            self.tick_timers();
+        }
+    }
+
+    // Counts the delay and sound timers down, has to be called 60 times per second
+    pub fn tick_timers(&mut self) {
+        let registers = &mut self.memory.registers;
+        registers.dt = registers.dt.saturating_sub(1);
+        registers.st = registers.st.saturating_sub(1);
+    }
+
+    // Like decode_at, but only decodes the instruction the first time it is executed
+    pub fn decode_cached(&mut self, addr: u16) -> Rc<dyn Opcode> {
+        let addr = self.memory.wrap(addr as usize);
+        if let Some(op) = &self.decode_cache[addr as usize] {
+            return Rc::clone(op);
+        }
+
+        let op: Rc<dyn Opcode> = Rc::from(self.decode_at(addr));
+        self.decode_cache[addr as usize] = Some(Rc::clone(&op));
+        op
+    }
+
+    // Has to be called whenever len bytes of RAM starting at addr are modified
+    pub fn invalidate_decode_cache(&mut self, addr: u16, len: usize) {
+        // The instruction starting one word earlier may have been a long one, that covered addr.
+        // Like the memory, the range continues at the beginning when it runs past the end.
+        let cache_len = self.decode_cache.len();
+        let start = addr as usize + cache_len - 2;
+        for offset in 0..std::cmp::min(len + 2, cache_len) {
+            self.decode_cache[(start + offset) % cache_len] = None;
         }
-        op.execute(self);
+    }
+
+    // Has to be called when the RAM has been modified from outside of the processor
+    pub fn clear_decode_cache(&mut self) {
+        self.decode_cache = vec![None; self.memory.ram.len()];
+    }
+
+    // Skips the instruction at pc, which is not necessarily two bytes long (XO-CHIP's F000 NNNN)
+    pub fn skip_instruction(&mut self) -> Result<(), MemoryError> {
+        let pc = self.memory.registers.pc;
+        let size = self.instruction_size(pc);
+        self.memory.registers.pc = self.memory.register_value(pc as usize + size as usize)?;
+        Ok(())
+    }
+
+    pub fn instruction_size(&mut self, addr: u16) -> u16 {
+        let opcode = self.memory.read_two_u8(addr);
+        self.instruction_set.instruction_size(opcode)
     }
 
     pub fn disassemble_tick(&mut self) {
@@ -35,119 +232,23 @@ impl Processor {
         self.memory.read_two_u8(self.memory.registers.pc)
     }
 
-    pub fn decode_opcode(&mut self, opcode: (u8, u8)) -> Box<dyn Opcode> {
-        //println!("=> Decoding {:#X} {:#X}", opcode.0, opcode.1);
+    // Decodes the instruction at addr, including the operand words of long instructions
+    pub fn decode_at(&mut self, addr: u16) -> Box<dyn Opcode> {
+        let opcode = self.memory.read_two_u8(addr);
+        if self.instruction_set.instruction_size(opcode) > 2 {
+            // A long instruction cut off by the end of the memory
+            if addr as usize + 4 > self.memory.ram.len() {
+                return Box::new(InvalidOpcode { opcode });
+            }
 
-        // Simple Opcodes which don't require bit shifting.
-        match opcode {
-            (0x0, 0xE0) => return Box::new(CLS {}),
-            (0x0, 0xEE) => return Box::new(RET {}),
-            _ => (),
-        };
-
-        // selector, the 4 highest bits
-        let sel = (opcode.0 & 0xF0) >> 4;
-        // value, the 2nd most highest 4 bits
-        let val = opcode.0 & 0x0F;
-
-        //println!("===> Decoded HIGH: selector={:#X}, value={:#X}", sel, val);
-
-        match sel {
-            1 => Box::new(JMP::new(val, opcode.1)),
-            2 => Box::new(CALL::new(val, opcode.1)),
-            3 => Box::new(SEVxByte {
-                reg: val,
-                byte: opcode.1,
-            }),
-            4 => Box::new(SNEVxByte {
-                reg: val,
-                byte: opcode.1,
-            }),
-            5 => Box::new(SEVxVy {
-                reg_a: val,
-                reg_b: ((opcode.1 & 0xF0) >> 4),
-            }),
-            6 => Box::new(LDVxByte {
-                reg: val,
-                byte: opcode.1,
-            }),
-            7 => Box::new(ADDVxByte {
-                reg: val,
-                byte: opcode.1,
-            }),
-            8 => match opcode.1 & 0x0F {
-                0 => Box::new(LDVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                1 => Box::new(ORVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                2 => Box::new(ANDVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                3 => Box::new(XORVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                4 => Box::new(ADDVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                5 => Box::new(SUBVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                6 => Box::new(SHRVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                7 => Box::new(SUBNVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                0xE => Box::new(SHLVxVy {
-                    reg_a: val,
-                    reg_b: ((opcode.1 & 0xF0) >> 4),
-                }),
-                _ => Box::new(InvalidOpcode { opcode }),
-            },
-            9 => Box::new(SNEVxVy {
-                reg_a: val,
-                reg_b: ((opcode.1 & 0xF0) >> 4),
-            }),
-            0xA => Box::new(LDIAddr::new(val, opcode.1)),
-            0xB => Box::new(JPV0Offset::new(val, opcode.1)),
-            0xC => Box::new(RNDVxByte {
-                reg: val,
-                byte: opcode.1,
-            }),
-            0xD => Box::new(DRW {
-                reg_x: val,
-                reg_y: ((opcode.1 & 0xF0) >> 4),
-                size: (opcode.1 & 0xF),
-            }),
-            0xE => match opcode.1 {
-                0x9E => Box::new(SKPKBRDVx { reg: val }),
-                0xA1 => Box::new(SKNPBRDVx { reg: val }),
-                _ => Box::new(InvalidOpcode { opcode }),
-            },
-
-            0xF => match opcode.1 {
-                7 => Box::new(LDVxDT { reg: val }),
-                0xA => Box::new(LDVxK { reg: val }),
-                0x15 => Box::new(LDDTVx { reg: val }),
-                0x18 => Box::new(LDSTVx { reg: val }),
-                0x1E => Box::new(ADDIVx { reg: val }),
-                0x29 => Box::new(LDFVx { reg: val }),
-                0x33 => Box::new(LDBVx { reg: val }),
-                0x55 => Box::new(LDIVx { reg: val }),
-                0x65 => Box::new(LDVxI { reg: val }),
-                _ => Box::new(InvalidOpcode { opcode }),
-            },
-            _ => Box::new(InvalidOpcode { opcode }),
+            let operand = self.memory.read_two_u8(addr + 2);
+            return self.instruction_set.decode_long(opcode, operand);
         }
+
+        self.decode_opcode(opcode)
+    }
+
+    pub fn decode_opcode(&mut self, opcode: (u8, u8)) -> Box<dyn Opcode> {
+        self.instruction_set.decode(opcode)
     }
 }
@@ -0,0 +1,219 @@
+use crate::internals::memory::Memory;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+
+// Where RNDVxByte gets its random numbers from. The memory is passed for sources which, like the
+// original interpreter, derive their numbers from it.
+pub trait RandomSource {
+    fn next_byte(&mut self, memory: &Memory) -> u8;
+
+    // Restarts the source, the same seed has to produce the same numbers again
+    fn reseed(&mut self, seed: u64);
+
+    // The state of the source as bytes, for save states and rewinding
+    fn snapshot(&self) -> Vec<u8>;
+
+    // Continues with the numbers that followed the snapshot, which has to be one of this source
+    fn restore(&mut self, snapshot: &[u8]) -> Result<(), String>;
+}
+
+fn snapshot_u64(snapshot: &[u8], at: usize) -> Result<u64, String> {
+    let mut bytes = [0; 8];
+    match snapshot.get(at..at + 8) {
+        Some(slice) => bytes.copy_from_slice(slice),
+        None => return Err("The random number state is too short".to_string()),
+    }
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn snapshot_u128(snapshot: &[u8], at: usize) -> Result<u128, String> {
+    let low = snapshot_u64(snapshot, at)? as u128;
+    let high = snapshot_u64(snapshot, at + 8)? as u128;
+    Ok(high << 64 | low)
+}
+
+// Uniformly distributed numbers from a seedable pseudo random number generator. Unlike rand's
+// StdRng, ChaCha8 is guaranteed to produce the same numbers on every platform and version, which
+// recorded replays and save states rely on.
+pub struct StdRandom {
+    rng: ChaCha8Rng,
+    seed: u64,
+    // The position in the stream of 32 bit words, every byte takes one of them. ChaCha8Rng can
+    // report it as well, but not before the first word has been generated.
+    word_pos: u128,
+}
+
+impl StdRandom {
+    pub fn new(seed: u64) -> StdRandom {
+        StdRandom {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            seed,
+            word_pos: 0,
+        }
+    }
+}
+
+impl RandomSource for StdRandom {
+    fn next_byte(&mut self, _memory: &Memory) -> u8 {
+        self.word_pos += 1;
+        self.rng.next_u32() as u8
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        *self = StdRandom::new(seed);
+    }
+
+    // The seed and the position in the stream of numbers it produces
+    fn snapshot(&self) -> Vec<u8> {
+        [
+            &self.seed.to_le_bytes()[..],
+            &self.word_pos.to_le_bytes()[..],
+        ]
+        .concat()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> Result<(), String> {
+        if snapshot.len() != 24 {
+            return Err("Not the state of the std random numbers".to_string());
+        }
+        let seed = snapshot_u64(snapshot, 0)?;
+        let word_pos = snapshot_u128(snapshot, 8)?;
+        // The stream is 2^68 words long
+        if word_pos >> 68 != 0 {
+            return Err("The position of the std random numbers is out of range".to_string());
+        }
+        if seed != self.seed {
+            self.reseed(seed);
+        }
+        self.rng.set_word_pos(word_pos);
+        self.word_pos = word_pos;
+        Ok(())
+    }
+}
+
+// Emulates the random routine of the COSMAC VIP interpreter: R9 is incremented and the byte it
+// points to is added to its high byte, which is the random number. Like on the original, the
+// numbers are far from uniform and depend on what is in memory. The interpreter itself isn't in
+// this emulator's memory though, so once R9 points to empty memory the number doesn't change.
+pub struct CosmacVip {
+    r9: u16,
+}
+
+impl CosmacVip {
+    pub fn new(seed: u64) -> CosmacVip {
+        CosmacVip { r9: seed as u16 }
+    }
+}
+
+impl RandomSource for CosmacVip {
+    fn next_byte(&mut self, memory: &Memory) -> u8 {
+        self.r9 = self.r9.wrapping_add(1);
+        let [high, low] = self.r9.to_be_bytes();
+        let byte = memory.ram[self.r9 as usize % memory.ram.len()];
+        let high = high.wrapping_add(byte);
+        self.r9 = u16::from_be_bytes([high, low]);
+        high
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.r9 = seed as u16;
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        self.r9.to_le_bytes().to_vec()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> Result<(), String> {
+        match snapshot {
+            [low, high] => {
+                self.r9 = u16::from_le_bytes([*low, *high]);
+                Ok(())
+            }
+            _ => Err("Not the state of the COSMAC VIP random numbers".to_string()),
+        }
+    }
+}
+
+// Returns the given bytes over and over again, so tests know which numbers they get
+pub struct FixedSequence {
+    bytes: Vec<u8>,
+    next: usize,
+}
+
+impl FixedSequence {
+    pub fn new(bytes: Vec<u8>) -> FixedSequence {
+        assert!(
+            !bytes.is_empty(),
+            "A fixed sequence needs at least one byte"
+        );
+        FixedSequence { bytes, next: 0 }
+    }
+}
+
+impl RandomSource for FixedSequence {
+    fn next_byte(&mut self, _memory: &Memory) -> u8 {
+        let byte = self.bytes[self.next];
+        self.next = (self.next + 1) % self.bytes.len();
+        byte
+    }
+
+    // Starts the sequence from the beginning, the seed is meaningless
+    fn reseed(&mut self, _seed: u64) {
+        self.next = 0;
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        (self.next as u64).to_le_bytes().to_vec()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> Result<(), String> {
+        match snapshot_u64(snapshot, 0) {
+            Ok(next) if snapshot.len() == 8 && next < self.bytes.len() as u64 => {
+                self.next = next as usize;
+                Ok(())
+            }
+            _ => Err("Not the state of this fixed sequence".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomMode {
+    Std,
+    Vip,
+}
+
+impl RandomMode {
+    pub fn source(self, seed: u64) -> Box<dyn RandomSource> {
+        match self {
+            RandomMode::Std => Box::new(StdRandom::new(seed)),
+            RandomMode::Vip => Box::new(CosmacVip::new(seed)),
+        }
+    }
+}
+
+impl FromStr for RandomMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "std" => Ok(RandomMode::Std),
+            "vip" => Ok(RandomMode::Vip),
+            _ => Err(format!("Unknown random mode {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for RandomMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RandomMode::Std => write!(f, "std"),
+            RandomMode::Vip => write!(f, "vip"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
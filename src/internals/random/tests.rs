@@ -0,0 +1,57 @@
+use super::*;
+
+fn bytes(source: &mut dyn RandomSource, count: usize) -> Vec<u8> {
+    let memory = Memory::default();
+    (0..count).map(|_| source.next_byte(&memory)).collect()
+}
+
+#[test]
+fn std_numbers_are_pinned() {
+    // Recorded replays and save states depend on these never changing
+    let mut source = StdRandom::new(0);
+    assert_eq!(
+        bytes(&mut source, 8),
+        vec![108, 103, 55, 95, 230, 50, 124, 164]
+    );
+}
+
+#[test]
+fn snapshots_continue_the_numbers() {
+    let sources: Vec<Box<dyn Fn() -> Box<dyn RandomSource>>> = vec![
+        Box::new(|| Box::new(StdRandom::new(42))),
+        Box::new(|| Box::new(CosmacVip::new(42))),
+        Box::new(|| Box::new(FixedSequence::new(vec![1, 2, 3]))),
+    ];
+    for new_source in sources.iter() {
+        let mut source = new_source();
+        bytes(&mut *source, 5);
+        let snapshot = source.snapshot();
+        let expected = bytes(&mut *source, 10);
+
+        // Going back as well as forward
+        source.restore(&snapshot).unwrap();
+        assert_eq!(bytes(&mut *source, 10), expected);
+        let mut restored = new_source();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(bytes(&mut *restored, 10), expected);
+    }
+}
+
+#[test]
+fn foreign_snapshots_are_rejected() {
+    let std = StdRandom::new(1).snapshot();
+    let vip = CosmacVip::new(1).snapshot();
+    assert!(CosmacVip::new(1).restore(&std).is_err());
+    assert!(StdRandom::new(1).restore(&vip).is_err());
+    assert!(FixedSequence::new(vec![1]).restore(&std).is_err());
+    // Past the end of the sequence
+    let fixed = FixedSequence::new(vec![1, 2, 3]).snapshot();
+    let mut longer = FixedSequence::new(vec![1, 2, 3, 4]);
+    longer.next_byte(&Memory::default());
+    longer.next_byte(&Memory::default());
+    longer.next_byte(&Memory::default());
+    assert!(FixedSequence::new(vec![1, 2, 3])
+        .restore(&longer.snapshot())
+        .is_err());
+    assert!(FixedSequence::new(vec![1, 2, 3]).restore(&fixed).is_ok());
+}
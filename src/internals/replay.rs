@@ -0,0 +1,164 @@
+use crate::internals::keyboard::Keyboard;
+use crate::internals::memory::Memory;
+use crate::internals::random::RandomMode;
+use crate::internals::savestate;
+use std::io::{self, BufRead, Write};
+
+// Input replay files are plain text:
+// c8in 1
+// seed <the seed of the random number generator>
+// random std|vip                 (the random number source, std if missing)
+// <cycle> <key> down|up          (one line per change of a key, key in hex)
+// end <cycle> <checksum>         (the last cycle and the FNV-1a hash of the RAM at that point)
+const HEADER: &str = "c8in 1";
+
+// A single key being pressed or released before the instruction with the given cycle number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub cycle: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub struct InputRecorder<W: Write> {
+    out: W,
+    keys: [bool; 16],
+}
+
+impl<W: Write> InputRecorder<W> {
+    pub fn new(mut out: W, seed: u64, random: RandomMode) -> io::Result<InputRecorder<W>> {
+        writeln!(out, "{}", HEADER)?;
+        writeln!(out, "seed {}", seed)?;
+        writeln!(out, "random {}", random)?;
+        Ok(InputRecorder {
+            out,
+            keys: [false; 16],
+        })
+    }
+
+    // Records every key that changed since the last call
+    pub fn record(&mut self, cycle: u64, keyboard: &Keyboard) -> io::Result<()> {
+        for (key, (old, new)) in self.keys.iter_mut().zip(keyboard.keys.iter()).enumerate() {
+            if old != new {
+                let state = if *new { "down" } else { "up" };
+                writeln!(self.out, "{} {:X} {}", cycle, key, state)?;
+                *old = *new;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(&mut self, cycle: u64, memory: &Memory) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "end {} {:016X}",
+            cycle,
+            savestate::hash(&memory.ram)
+        )?;
+        self.out.flush()
+    }
+}
+
+pub struct InputReplay {
+    pub seed: u64,
+    pub random: RandomMode,
+    events: Vec<InputEvent>,
+    next_event: usize,
+    pub end_cycle: u64,
+    checksum: u64,
+}
+
+impl InputReplay {
+    pub fn read<R: BufRead>(input: R) -> io::Result<InputReplay> {
+        let mut lines = input.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            return Err(invalid_data("Not an input replay file".to_string()));
+        }
+
+        let mut seed = None;
+        let mut random = RandomMode::Std;
+        let mut end = None;
+        let mut events = Vec::new();
+        for line in lines {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let invalid_line = || invalid_data(format!("Invalid line in input replay: {}", line));
+
+            match fields.as_slice() {
+                [] => (),
+                ["seed", value] => seed = Some(value.parse().map_err(|_| invalid_line())?),
+                ["random", mode] => random = mode.parse().map_err(|_| invalid_line())?,
+                ["end", cycle, checksum] => {
+                    end = Some((
+                        cycle.parse().map_err(|_| invalid_line())?,
+                        u64::from_str_radix(checksum, 16).map_err(|_| invalid_line())?,
+                    ))
+                }
+                [cycle, key, state] => events.push(InputEvent {
+                    cycle: cycle.parse().map_err(|_| invalid_line())?,
+                    key: u8::from_str_radix(key, 16)
+                        .ok()
+                        .filter(|key| *key < 16)
+                        .ok_or_else(invalid_line)?,
+                    pressed: match *state {
+                        "down" => true,
+                        "up" => false,
+                        _ => return Err(invalid_line()),
+                    },
+                }),
+                _ => return Err(invalid_line()),
+            }
+        }
+
+        let seed = seed.ok_or_else(|| invalid_data("The input replay has no seed".to_string()))?;
+        let (end_cycle, checksum) = end.ok_or_else(|| {
+            invalid_data("The input replay is incomplete, it has no end".to_string())
+        })?;
+
+        Ok(InputReplay {
+            seed,
+            random,
+            events,
+            next_event: 0,
+            end_cycle,
+            checksum,
+        })
+    }
+
+    // Applies every event up to (and including) the given cycle to the keyboard
+    pub fn apply(&mut self, cycle: u64, keyboard: &mut Keyboard) {
+        while let Some(event) = self.events.get(self.next_event) {
+            if event.cycle > cycle {
+                break;
+            }
+
+            keyboard.keys[event.key as usize] = event.pressed;
+            self.next_event += 1;
+        }
+    }
+
+    pub fn is_finished(&self, cycle: u64) -> bool {
+        cycle >= self.end_cycle
+    }
+
+    // Whether the memory is the same as when the input has been recorded
+    pub fn verify(&self, memory: &Memory) -> Result<(), String> {
+        let checksum = savestate::hash(&memory.ram);
+        if checksum == self.checksum {
+            Ok(())
+        } else {
+            Err(format!(
+                "The memory differs from the recording: checksum {:016X} instead of {:016X}",
+                checksum, self.checksum
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
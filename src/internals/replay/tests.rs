@@ -0,0 +1,97 @@
+use super::*;
+use crate::internals::processor::Processor;
+use crate::internals::variants::Variant;
+
+fn read(text: &str) -> io::Result<InputReplay> {
+    InputReplay::read(text.as_bytes())
+}
+
+fn keys(pressed: &[usize]) -> Keyboard {
+    let mut keyboard = Keyboard::default();
+    for key in pressed {
+        keyboard.keys[*key] = true;
+    }
+    keyboard
+}
+
+#[test]
+fn round_trip() {
+    let mut out = Vec::new();
+    let mut recorder = InputRecorder::new(&mut out, 42, RandomMode::Vip).unwrap();
+    recorder.record(0, &keys(&[])).unwrap();
+    recorder.record(10, &keys(&[0xA, 3])).unwrap();
+    recorder.record(25, &keys(&[3])).unwrap();
+    recorder.finish(30, &Memory::default()).unwrap();
+
+    let mut replay = InputReplay::read(&out[..]).unwrap();
+    assert_eq!((replay.seed, replay.random), (42, RandomMode::Vip));
+    assert_eq!(replay.end_cycle, 30);
+    assert!(replay.verify(&Memory::default()).is_ok());
+
+    let mut keyboard = Keyboard::default();
+    replay.apply(9, &mut keyboard);
+    assert_eq!(keyboard.keys, keys(&[]).keys);
+    replay.apply(10, &mut keyboard);
+    assert_eq!(keyboard.keys, keys(&[3, 0xA]).keys);
+    replay.apply(30, &mut keyboard);
+    assert_eq!(keyboard.keys, keys(&[3]).keys);
+}
+
+#[test]
+fn parse_errors() {
+    let replay = read("c8in 1\nseed 7\nend 5 0\n").unwrap();
+    assert_eq!(replay.random, RandomMode::Std);
+
+    let error = |text| read(text).err().unwrap().to_string();
+    assert_eq!(error("c8in 2\n"), "Not an input replay file");
+    assert_eq!(error("c8in 1\nend 5 0\n"), "The input replay has no seed");
+    assert_eq!(
+        error("c8in 1\nseed 7\n"),
+        "The input replay is incomplete, it has no end"
+    );
+    assert_eq!(
+        error("c8in 1\nseed 7\nrandom dice\nend 5 0\n"),
+        "Invalid line in input replay: random dice"
+    );
+    assert_eq!(
+        error("c8in 1\nseed 7\n3 10 down\nend 5 0\n"),
+        "Invalid line in input replay: 3 10 down"
+    );
+}
+
+// Stores random numbers one after another while the key 5 is held, until the replay ends
+fn run(replay: &mut InputReplay) -> Processor {
+    let program = [
+        0xA300, 0x6105, 0xE1A1, 0x120A, 0x1204, 0xC0FF, 0xF055, 0x1204,
+    ];
+    let mut processor = Processor::with_program(Variant::Chip8, &program);
+    processor.rng = replay.random.source(replay.seed);
+    processor.reseed(replay.seed);
+
+    while !replay.is_finished(processor.cycles) {
+        replay.apply(processor.cycles, &mut processor.keyboard);
+        processor.run_frame().unwrap();
+        processor.tick_timers();
+    }
+    processor
+}
+
+#[test]
+fn replays_are_deterministic() {
+    for random in ["std", "vip"].iter() {
+        let text = format!(
+            "c8in 1\nseed 99\nrandom {}\n20 5 down\n60 5 up\nend 100 0\n",
+            random
+        );
+        let first = run(&mut read(&text).unwrap());
+        let second = run(&mut read(&text).unwrap());
+        assert_eq!(first.cycles, 100);
+        assert!(first.memory.ram == second.memory.ram, "{}", random);
+        assert_ne!(first.memory.ram[0x300], 0);
+    }
+
+    // The mode changes the numbers
+    let std = run(&mut read("c8in 1\nseed 99\nrandom std\n0 5 down\nend 50 0\n").unwrap());
+    let vip = run(&mut read("c8in 1\nseed 99\nrandom vip\n0 5 down\nend 50 0\n").unwrap());
+    assert!(std.memory.ram[0x300..0x310] != vip.memory.ram[0x300..0x310]);
+}
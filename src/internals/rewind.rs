@@ -0,0 +1,140 @@
+use crate::internals::fault::Fault;
+use crate::internals::layout::{self, Layout};
+use crate::internals::memory::Registers;
+use crate::internals::processor::Processor;
+use std::collections::{HashSet, VecDeque};
+
+// Everything needed to undo a single instruction
+struct UndoRecord {
+    registers: Registers,
+    stack: Vec<u16>,
+    // The previous values of the RAM bytes the instruction wrote to
+    ram: Vec<(usize, u8)>,
+    // The previous values of the display rows (plane, y) the instruction changed
+    rows: Vec<(usize, usize, u128)>,
+    hires: bool,
+    planes: u8,
+    halted: bool,
+    cycles: u64,
+    frame_cycles: u32,
+    rng: Vec<u8>,
+}
+
+// A bounded history of the executed instructions, so they can be undone again. Once it is full,
+// the oldest instructions are forgotten.
+pub struct History {
+    records: VecDeque<UndoRecord>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    // Executes a single instruction using Processor::tick and remembers how to undo it, even
+    // when it fails
+    pub fn tick(&mut self, processor: &mut Processor) -> Result<(), Fault> {
+        let pc = processor.memory.registers.pc;
+        let instruction = processor.decode_cached(pc).instruction();
+        let written = instruction.writes_memory(processor.memory.registers.i);
+        let ram = written
+            .map(|addr| processor.memory.wrap(addr) as usize)
+            .map(|addr| (addr, processor.memory.ram[addr]))
+            .collect();
+
+        let bitplanes = processor.display.bitplanes;
+        let mut record = UndoRecord {
+            registers: processor.memory.registers.clone(),
+            stack: processor.memory.stack.clone(),
+            ram,
+            rows: Vec::new(),
+            hires: processor.display.hires,
+            planes: processor.display.planes,
+            halted: processor.halted,
+            cycles: processor.cycles,
+            frame_cycles: processor.frame_cycles,
+            rng: processor.rng.snapshot(),
+        };
+
+        let result = processor.tick();
+
+        for (plane, (old, new)) in bitplanes
+            .iter()
+            .zip(processor.display.bitplanes.iter())
+            .enumerate()
+        {
+            for (y, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+                if old != new {
+                    record.rows.push((plane, y, *old));
+                }
+            }
+        }
+
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+        result
+    }
+
+    // Undoes the last instruction, returns false if there is nothing left to undo
+    pub fn step_back(&mut self, processor: &mut Processor) -> bool {
+        let record = match self.records.pop_back() {
+            Some(record) => record,
+            None => return false,
+        };
+
+        processor.memory.registers = record.registers;
+        processor.memory.stack = record.stack;
+        for (addr, value) in record.ram.iter() {
+            processor.memory.ram[*addr] = *value;
+            processor.invalidate_decode_cache(*addr as u16, 1);
+        }
+
+        for (plane, y, row) in record.rows.iter() {
+            processor.display.bitplanes[*plane][*y] = *row;
+        }
+        processor.display.hires = record.hires;
+        processor.display.planes = record.planes;
+        processor.display.mark_all_dirty();
+        processor.halted = record.halted;
+        processor.cycles = record.cycles;
+        processor.frame_cycles = record.frame_cycles;
+        processor
+            .rng
+            .restore(&record.rng)
+            .expect("The random number source changed while rewinding");
+        // The mirrored parts of the RAM aren't part of the record
+        if processor.layout == Layout::Vip {
+            layout::store(processor);
+        }
+
+        true
+    }
+
+    // Steps back at least once and then until a breakpoint is reached, so starting at a
+    // breakpoint reaches the previous one. Returns false if the history ran out before.
+    pub fn step_back_to(&mut self, processor: &mut Processor, breakpoints: &HashSet<u16>) -> bool {
+        while self.step_back(processor) {
+            if breakpoints.contains(&processor.memory.registers.pc) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests;
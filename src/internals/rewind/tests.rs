@@ -0,0 +1,113 @@
+use super::*;
+use crate::internals::variants::Variant;
+
+// Stores a random number, draws a digit and calls a subroutine clearing the screen again
+const PROGRAM: [u16; 10] = [
+    0xA300, 0x6042, 0xC1FF, 0xF155, 0xA050, 0xD015, 0x2210, 0x120E, 0x00E0, 0x00EE,
+];
+
+fn processor() -> Processor {
+    let mut processor = Processor::with_program(Variant::Chip8, &PROGRAM);
+    processor.reseed(7);
+    processor
+}
+
+// Everything an instruction may change
+type State = (
+    Registers,
+    Vec<u16>,
+    Vec<u8>,
+    [[u128; 64]; 2],
+    u64,
+    u32,
+    Vec<u8>,
+);
+
+fn state(processor: &Processor) -> State {
+    (
+        processor.memory.registers.clone(),
+        processor.memory.stack.clone(),
+        processor.memory.ram.clone(),
+        processor.display.bitplanes,
+        processor.cycles,
+        processor.frame_cycles,
+        processor.rng.snapshot(),
+    )
+}
+
+#[test]
+fn step_back_restores_everything() {
+    let mut p = processor();
+    let mut history = History::new(16);
+    let mut states = Vec::new();
+    for _ in 0..9 {
+        states.push(state(&p));
+        history.tick(&mut p).unwrap();
+    }
+    let end = state(&p);
+
+    while let Some(expected) = states.pop() {
+        assert!(history.step_back(&mut p));
+        let actual = state(&p);
+        assert_eq!(actual.0, expected.0);
+        assert_eq!(actual.1, expected.1);
+        assert!(
+            actual.2 == expected.2,
+            "The RAM differs at {:#X}",
+            actual.0.pc
+        );
+        assert!(
+            actual.3 == expected.3,
+            "The display differs at {:#X}",
+            actual.0.pc
+        );
+        assert_eq!((actual.4, actual.5), (expected.4, expected.5));
+        assert_eq!(actual.6, expected.6);
+    }
+    assert!(!history.step_back(&mut p));
+
+    // The same random number is drawn again
+    for _ in 0..9 {
+        history.tick(&mut p).unwrap();
+    }
+    let again = state(&p);
+    assert_eq!(again.0, end.0);
+    assert!(again.2 == end.2);
+    assert_eq!(again.6, end.6);
+}
+
+#[test]
+fn oldest_records_are_forgotten() {
+    let mut p = processor();
+    let mut history = History::new(3);
+    for _ in 0..5 {
+        history.tick(&mut p).unwrap();
+    }
+    assert_eq!(history.len(), 3);
+
+    for _ in 0..3 {
+        assert!(history.step_back(&mut p));
+    }
+    assert!(history.is_empty());
+    assert!(!history.step_back(&mut p));
+    assert_eq!(p.memory.registers.pc, 0x204);
+    assert_eq!(p.cycles, 2);
+}
+
+#[test]
+fn step_back_to_the_previous_breakpoint() {
+    let mut p = processor();
+    let mut history = History::new(16);
+    for _ in 0..6 {
+        history.tick(&mut p).unwrap();
+    }
+    assert_eq!(p.memory.registers.pc, 0x20C);
+
+    let breakpoints: HashSet<u16> = [0x202, 0x208, 0x20C].iter().copied().collect();
+    assert!(history.step_back_to(&mut p, &breakpoints));
+    assert_eq!(p.memory.registers.pc, 0x208);
+    assert!(history.step_back_to(&mut p, &breakpoints));
+    assert_eq!(p.memory.registers.pc, 0x202);
+    assert!(!history.step_back_to(&mut p, &breakpoints));
+    assert_eq!(p.memory.registers.pc, 0x200);
+}
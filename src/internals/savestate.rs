@@ -0,0 +1,246 @@
+use crate::internals::display::{HIRES_HEIGHT, PLANES};
+use crate::internals::font::{BIG_FONT_LEN, FONT_LEN};
+use crate::internals::layout::Layout;
+use crate::internals::memory::Addressing;
+use crate::internals::processor::Processor;
+use crate::internals::timing::Timing;
+use std::io::{self, Read, Write};
+
+// Save state format, all numbers little endian:
+// magic "EMUL8SAV", version (u16), variant name (u8 length + bytes), ROM hash (u64),
+// registers (pc, sp, i, dt, st, v, rpl, pattern, pitch), stack (u8 depth + entries),
+// RAM (u32 length + bytes),
+// display (hires, planes, bitplanes as u128 rows), keys, halted,
+// cycles (u64), frame cycles (u32), seed (u64), random number state (u8 length + bytes),
+// font address (u16), addressing, layout and timing (u8 each)
+const MAGIC: &[u8; 8] = b"EMUL8SAV";
+const VERSION: u16 = 3;
+
+// 64 bit FNV-1a, used to recognize the ROM (and the memory contents) a state belongs to
+pub fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_bytes<R: Read, const N: usize>(input: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8<R: Read>(input: &mut R) -> io::Result<u8> {
+    Ok(read_bytes::<R, 1>(input)?[0])
+}
+
+fn read_u16<R: Read>(input: &mut R) -> io::Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(input)?))
+}
+
+fn read_u64<R: Read>(input: &mut R) -> io::Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(input)?))
+}
+
+// The enums are stored as the index of their variant
+fn read_enum<R: Read, T: Copy>(input: &mut R, variants: &[T], what: &str) -> io::Result<T> {
+    let index = read_u8(input)?;
+    variants
+        .get(index as usize)
+        .copied()
+        .ok_or_else(|| invalid_data(format!("Unknown {} {}", what, index)))
+}
+
+pub fn write<W: Write>(processor: &Processor, out: &mut W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    let name = processor.instruction_set.name();
+    out.write_all(&[name.len() as u8])?;
+    out.write_all(name.as_bytes())?;
+    out.write_all(&processor.rom_hash.to_le_bytes())?;
+
+    let registers = &processor.memory.registers;
+    out.write_all(&registers.pc.to_le_bytes())?;
+    out.write_all(&registers.sp.to_le_bytes())?;
+    out.write_all(&registers.i.to_le_bytes())?;
+    out.write_all(&[registers.dt, registers.st])?;
+    out.write_all(&registers.v)?;
+    out.write_all(&registers.rpl)?;
+    out.write_all(&registers.pattern)?;
+    out.write_all(&[registers.pitch])?;
+    out.write_all(&[processor.memory.stack.len() as u8])?;
+    for entry in processor.memory.stack.iter() {
+        out.write_all(&entry.to_le_bytes())?;
+    }
+    out.write_all(&(processor.memory.ram.len() as u32).to_le_bytes())?;
+    out.write_all(&processor.memory.ram)?;
+
+    let display = &processor.display;
+    out.write_all(&[display.hires as u8, display.planes])?;
+    for row in display.bitplanes.iter().flatten() {
+        out.write_all(&row.to_le_bytes())?;
+    }
+
+    let keys: Vec<u8> = processor
+        .keyboard
+        .keys
+        .iter()
+        .map(|&key| key as u8)
+        .collect();
+    out.write_all(&keys)?;
+    out.write_all(&[processor.halted as u8])?;
+
+    out.write_all(&processor.cycles.to_le_bytes())?;
+    out.write_all(&processor.frame_cycles.to_le_bytes())?;
+    out.write_all(&processor.seed.to_le_bytes())?;
+    let rng = processor.rng.snapshot();
+    out.write_all(&[rng.len() as u8])?;
+    out.write_all(&rng)?;
+    out.write_all(&processor.memory.font_addr.to_le_bytes())?;
+    out.write_all(&[
+        processor.memory.addressing as u8,
+        processor.layout as u8,
+        processor.timing as u8,
+    ])
+}
+
+// Only modifies the processor once the whole state has been read and checked
+pub fn read<R: Read>(processor: &mut Processor, input: &mut R) -> io::Result<()> {
+    if &read_bytes::<R, 8>(input)? != MAGIC {
+        return Err(invalid_data("Not a save state".to_string()));
+    }
+
+    let version = read_u16(input)?;
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "Unsupported save state version {}",
+            version
+        )));
+    }
+
+    let mut name = vec![0; read_u8(input)? as usize];
+    input.read_exact(&mut name)?;
+    let name = String::from_utf8_lossy(&name);
+    if name != processor.instruction_set.name() {
+        return Err(invalid_data(format!(
+            "The save state has been made with variant {}, not {}",
+            name,
+            processor.instruction_set.name()
+        )));
+    }
+
+    if u64::from_le_bytes(read_bytes(input)?) != processor.rom_hash {
+        return Err(invalid_data(
+            "The save state has been made with a different ROM".to_string(),
+        ));
+    }
+
+    let pc = read_u16(input)?;
+    let sp = i8::from_le_bytes(read_bytes(input)?);
+    let i = read_u16(input)?;
+    let [dt, st] = read_bytes(input)?;
+    let v = read_bytes(input)?;
+    let rpl = read_bytes(input)?;
+    let pattern = read_bytes(input)?;
+    let pitch = read_u8(input)?;
+    let mut stack = vec![0; read_u8(input)? as usize];
+    for entry in stack.iter_mut() {
+        *entry = read_u16(input)?;
+    }
+    if sp < -1 || sp as isize >= stack.len() as isize {
+        return Err(invalid_data(format!(
+            "The stack pointer {} lies outside of the stack",
+            sp
+        )));
+    }
+
+    let ram_len = u32::from_le_bytes(read_bytes(input)?) as usize;
+    if ram_len != processor.memory.ram.len() {
+        return Err(invalid_data(format!(
+            "The save state has {} bytes of memory instead of {}",
+            ram_len,
+            processor.memory.ram.len()
+        )));
+    }
+    let mut ram = vec![0; ram_len];
+    input.read_exact(&mut ram)?;
+
+    let [hires, planes] = read_bytes(input)?;
+    let mut bitplanes = [[0; HIRES_HEIGHT]; PLANES];
+    for row in bitplanes.iter_mut().flatten() {
+        *row = u128::from_le_bytes(read_bytes(input)?);
+    }
+    let keys: [u8; 16] = read_bytes(input)?;
+    let halted = read_u8(input)?;
+
+    let cycles = read_u64(input)?;
+    let frame_cycles = u32::from_le_bytes(read_bytes(input)?);
+    let seed = read_u64(input)?;
+    let mut rng = vec![0; read_u8(input)? as usize];
+    input.read_exact(&mut rng)?;
+    let font_addr = read_u16(input)?;
+    if processor
+        .memory
+        .range(font_addr, FONT_LEN + BIG_FONT_LEN)
+        .is_err()
+    {
+        return Err(invalid_data(format!(
+            "The font at {:#X} lies outside of the memory",
+            font_addr
+        )));
+    }
+    let addressing = read_enum(
+        input,
+        &[Addressing::Strict, Addressing::Wrap12, Addressing::Extended],
+        "addressing mode",
+    )?;
+    let layout = read_enum(input, &[Layout::Flat, Layout::Vip], "memory layout")?;
+    let timing = read_enum(input, &[Timing::Fixed, Timing::Vip], "timing")?;
+    // The last check, as it already restores the random number source. Every source validates
+    // the snapshot before restoring it, which takes constant time.
+    processor.rng.restore(&rng).map_err(|error| {
+        invalid_data(format!(
+            "Unable to continue the random numbers of the save state: {}",
+            error
+        ))
+    })?;
+
+    let registers = &mut processor.memory.registers;
+    registers.pc = pc;
+    registers.sp = sp;
+    registers.i = i;
+    registers.dt = dt;
+    registers.st = st;
+    registers.v = v;
+    registers.rpl = rpl;
+    registers.pattern = pattern;
+    registers.pitch = pitch;
+    processor.memory.stack = stack;
+    processor.memory.ram = ram;
+    processor.clear_decode_cache();
+
+    processor.display.hires = hires != 0;
+    processor.display.planes = planes;
+    processor.display.bitplanes = bitplanes;
+    processor.display.mark_all_dirty();
+    for (key, state) in processor.keyboard.keys.iter_mut().zip(keys.iter()) {
+        *key = *state != 0;
+    }
+    processor.halted = halted != 0;
+
+    processor.cycles = cycles;
+    processor.frame_cycles = frame_cycles;
+    processor.seed = seed;
+    processor.memory.font_addr = font_addr;
+    processor.memory.addressing = addressing;
+    processor.layout = layout;
+    processor.timing = timing;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
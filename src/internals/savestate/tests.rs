@@ -0,0 +1,131 @@
+use super::*;
+use crate::internals::font::Font;
+use crate::internals::random::{CosmacVip, RandomMode};
+use crate::internals::variants::Variant;
+
+fn processor(variant: Variant) -> Processor {
+    let mut processor = Processor::new(variant.instruction_set());
+    processor.rom_hash = 0x1234;
+    processor.reseed(3);
+    processor
+}
+
+fn save(processor: &Processor) -> Vec<u8> {
+    let mut state = Vec::new();
+    processor.save_state(&mut state).unwrap();
+    state
+}
+
+fn error(processor: &mut Processor, state: &[u8]) -> String {
+    processor
+        .load_state(&mut &state[..])
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn round_trip() {
+    let mut p = processor(Variant::Chip8);
+    p.set_font(&Font::default(), 0x100).unwrap();
+    p.memory.addressing = Addressing::Strict;
+    p.layout = Layout::Vip;
+    p.timing = Timing::Vip;
+    p.memory.stack = vec![0; 12];
+    // Draws a random byte and stores it
+    p.load_program(&[0xC0FF, 0xA300, 0xF055]);
+    p.keyboard.keys[5] = true;
+    for _ in 0..3 {
+        p.tick().unwrap();
+    }
+    p.display.draw_row(3, 4, 1 << 127, 1);
+    let state = save(&p);
+
+    let mut loaded = processor(Variant::Chip8);
+    loaded.reseed(99);
+    loaded.load_state(&mut &state[..]).unwrap();
+    assert_eq!(loaded.memory.registers, p.memory.registers);
+    assert_eq!(loaded.memory.stack, p.memory.stack);
+    assert!(loaded.memory.ram == p.memory.ram);
+    assert!(loaded.display.bitplanes == p.display.bitplanes);
+    assert_eq!(loaded.keyboard.keys, p.keyboard.keys);
+    assert_eq!(
+        (loaded.cycles, loaded.frame_cycles, loaded.seed),
+        (p.cycles, p.frame_cycles, p.seed)
+    );
+    assert_eq!(loaded.rng.snapshot(), p.rng.snapshot());
+    assert_eq!(loaded.memory.font_addr, 0x100);
+    assert_eq!(loaded.memory.addressing, Addressing::Strict);
+    assert_eq!(loaded.layout, Layout::Vip);
+    assert_eq!(loaded.timing, Timing::Vip);
+    assert_eq!(save(&loaded), state);
+}
+
+#[test]
+fn random_numbers_continue() {
+    let mut p = processor(Variant::Chip8);
+    p.rng = RandomMode::Vip.source(0x42);
+    p.rng.next_byte(&p.memory);
+    let state = save(&p);
+    let expected = p.rng.next_byte(&p.memory);
+
+    let mut loaded = processor(Variant::Chip8);
+    loaded.rng = Box::new(CosmacVip::new(0));
+    loaded.load_state(&mut &state[..]).unwrap();
+    assert_eq!(loaded.rng.next_byte(&loaded.memory), expected);
+
+    // The std random numbers can't continue the VIP ones
+    let mut loaded = processor(Variant::Chip8);
+    assert!(error(&mut loaded, &state)
+        .starts_with("Unable to continue the random numbers of the save state"));
+}
+
+#[test]
+fn rejected_states() {
+    let p = processor(Variant::SuperChip);
+    let state = save(&p);
+    let mut loaded = processor(Variant::SuperChip);
+
+    let mut wrong_magic = state.clone();
+    wrong_magic[0] = b'X';
+    assert_eq!(error(&mut loaded, &wrong_magic), "Not a save state");
+
+    let mut wrong_version = state.clone();
+    wrong_version[8..10].copy_from_slice(&2u16.to_le_bytes());
+    assert_eq!(
+        error(&mut loaded, &wrong_version),
+        "Unsupported save state version 2"
+    );
+
+    let mut chip8 = processor(Variant::Chip8);
+    assert!(error(&mut chip8, &state).starts_with("The save state has been made with variant"));
+
+    loaded.rom_hash = 0x4321;
+    assert_eq!(
+        error(&mut loaded, &state),
+        "The save state has been made with a different ROM"
+    );
+
+    // Nothing has been changed by the failed attempts
+    assert_eq!(save(&chip8), save(&processor(Variant::Chip8)));
+}
+
+#[test]
+fn random_number_positions() {
+    let p = processor(Variant::Chip8);
+    let mut state = save(&p);
+    // The position follows the seed, before the font address and the three enums
+    let position = state.len() - 5 - 16..state.len() - 5;
+
+    // Far into the stream, which must not be generated word by word
+    state[position.clone()].copy_from_slice(&(u64::MAX as u128).to_le_bytes());
+    let mut loaded = processor(Variant::Chip8);
+    loaded.load_state(&mut &state[..]).unwrap();
+    assert_eq!(save(&loaded), state);
+
+    // Past the end of the stream
+    state[position].copy_from_slice(&u128::MAX.to_le_bytes());
+    let mut loaded = processor(Variant::Chip8);
+    assert!(error(&mut loaded, &state)
+        .starts_with("Unable to continue the random numbers of the save state"));
+    assert_eq!(save(&loaded), save(&processor(Variant::Chip8)));
+}
@@ -0,0 +1,27 @@
+use crate::internals::processor::Processor;
+use crate::internals::variants::Variant;
+
+// Where the programs of the tests are loaded and started, like ROMs
+pub const START: u16 = 0x200;
+
+// The bytes of a program written as instruction words
+pub fn program_bytes(program: &[u16]) -> Vec<u8> {
+    program.iter().flat_map(|word| word.to_be_bytes()).collect()
+}
+
+impl Processor {
+    // A processor about to run the program, which never waits for the terminal
+    pub fn with_program(variant: Variant, program: &[u16]) -> Processor {
+        let mut processor = Processor::new(variant.instruction_set());
+        processor.keyboard.line_input = false;
+        processor.load_program(program);
+        processor
+    }
+
+    // Replaces the program at START and jumps to it, keeping everything else
+    pub fn load_program(&mut self, program: &[u16]) {
+        self.memory.load(&program_bytes(program), START);
+        self.clear_decode_cache();
+        self.memory.registers.pc = START;
+    }
+}
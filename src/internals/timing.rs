@@ -0,0 +1,109 @@
+use crate::internals::instruction::Instruction;
+use crate::internals::memory::Memory;
+use crate::internals::processor::TICKS_PER_FRAME;
+use std::str::FromStr;
+
+// The machine cycles (8 clock cycles of the 1.76 MHz CDP1802) the COSMAC VIP can spend on the
+// interpreter per frame: 1760900 / 8 / 60 = 3668, minus the 1024 the display DMA takes
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668 - 1024;
+
+// How many instructions are executed per frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    // Every instruction costs the same, TICKS_PER_FRAME of them run per frame
+    Fixed,
+    // Every instruction costs what it took on the COSMAC VIP, see vip_cycles. DRW waits for the
+    // next frame, like the original interpreter does to avoid flickering.
+    Vip,
+}
+
+impl Timing {
+    // What the instructions of a single frame may cost in total
+    pub fn frame_budget(self) -> u32 {
+        match self {
+            Timing::Fixed => TICKS_PER_FRAME as u32,
+            Timing::Vip => VIP_CYCLES_PER_FRAME,
+        }
+    }
+}
+
+impl FromStr for Timing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Timing::Fixed),
+            "vip" => Ok(Timing::Vip),
+            _ => Err(format!("Unknown timing {}", s)),
+        }
+    }
+}
+
+// The machine cycles the instruction takes on the COSMAC VIP, including fetching and decoding
+// it, given the state of the memory before it is executed. These are the execution times the
+// original interpreter has been measured to take ("Chip-8 Instruction Scheduling and Frequency",
+// jackson-s.me, 2019) at 4.54 µs per machine cycle. Where they depend on the operands, the
+// averages have been split into the fixed and the variable part.
+pub fn vip_cycles(instruction: &Instruction, memory: &Memory) -> u32 {
+    let v = |reg: u8| memory.registers.v[reg as usize] as u32;
+    match *instruction {
+        Instruction::CLS => 24,
+        Instruction::RET
+        | Instruction::JMP { .. }
+        | Instruction::CALL { .. }
+        | Instruction::JPV0Offset { .. } => 23,
+        Instruction::SEVxByte { .. } | Instruction::SNEVxByte { .. } => 12,
+        Instruction::SEVxVy { .. } | Instruction::SNEVxVy { .. } => 16,
+        Instruction::LDVxByte { .. } => 6,
+        Instruction::ADDVxByte { .. } => 10,
+        Instruction::LDVxVy { .. }
+        | Instruction::ORVxVy { .. }
+        | Instruction::ANDVxVy { .. }
+        | Instruction::XORVxVy { .. }
+        | Instruction::ADDVxVy { .. }
+        | Instruction::SUBVxVy { .. }
+        | Instruction::SHRVxVy { .. }
+        | Instruction::SUBNVxVy { .. }
+        | Instruction::SHLVxVy { .. } => 44,
+        Instruction::LDIAddr { .. } => 12,
+        Instruction::RNDVxByte { .. } => 36,
+        // Sprites not starting at a byte boundary have to be shifted into place, bit by bit. The
+        // wait for the vertical blank afterwards is up to Processor::tick.
+        Instruction::DRW { reg_x, size, .. } => 68 + size as u32 * (20 + 6 * (v(reg_x) % 8)),
+        Instruction::SKPKBRDVx { .. } | Instruction::SKNPBRDVx { .. } => 16,
+        // Waiting for the key is up to the keyboard
+        Instruction::LDVxDT { .. }
+        | Instruction::LDVxK { .. }
+        | Instruction::LDDTVx { .. }
+        | Instruction::LDSTVx { .. } => 10,
+        Instruction::ADDIVx { .. } => 19,
+        Instruction::LDFVx { .. } => 20,
+        // The digits are found by repeated subtraction
+        Instruction::LDBVx { reg } => {
+            let vx = v(reg);
+            48 + 16 * (vx / 100 + vx / 10 % 10 + vx % 10)
+        }
+        // A loop over V0 to Vx
+        Instruction::LDIVx { reg } | Instruction::LDVxI { reg } => 14 + 14 * (reg as u32 + 1),
+        // The VIP doesn't know these, charge them like a jump
+        Instruction::InvalidOpcode { .. }
+        | Instruction::SCD { .. }
+        | Instruction::SCR
+        | Instruction::SCL
+        | Instruction::EXIT
+        | Instruction::LOW
+        | Instruction::HIGH
+        | Instruction::LDHFVx { .. }
+        | Instruction::LDRVx { .. }
+        | Instruction::LDVxR { .. }
+        | Instruction::LDILong { .. }
+        | Instruction::SAVEVxVy { .. }
+        | Instruction::LOADVxVy { .. }
+        | Instruction::PLANE { .. }
+        | Instruction::AUDIO
+        | Instruction::PITCHVx { .. } => 23,
+    }
+}
+
+#[cfg(test)]
+mod tests;
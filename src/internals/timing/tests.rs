@@ -0,0 +1,110 @@
+use super::*;
+use crate::internals::processor::Processor;
+use crate::internals::variants::Variant;
+
+fn processor(timing: Timing, program: &[u16]) -> Processor {
+    let mut processor = Processor::with_program(Variant::Chip8, program);
+    processor.timing = timing;
+    processor
+}
+
+#[test]
+fn fixed_timing() {
+    // ADD V0, 1 and JP 0x200
+    let mut p = processor(Timing::Fixed, &[0x7001, 0x1200]);
+    p.run_frame().unwrap();
+    assert_eq!(p.cycles, TICKS_PER_FRAME as u64);
+    p.run_frame().unwrap();
+    assert_eq!(p.cycles, 2 * TICKS_PER_FRAME as u64);
+}
+
+#[test]
+fn single_steps_end_frames() {
+    let mut p = processor(Timing::Fixed, &[0x7001, 0x1200]);
+    p.memory.registers.dt = 5;
+    for _ in 1..TICKS_PER_FRAME {
+        p.tick().unwrap();
+        p.end_full_frame();
+    }
+    assert_eq!(p.memory.registers.dt, 5);
+    p.tick().unwrap();
+    p.end_full_frame();
+    assert_eq!((p.memory.registers.dt, p.frame_cycles), (4, 0));
+
+    // Drawing ends the frame on the VIP
+    let mut p = processor(Timing::Vip, &[0xD005, 0x1200]);
+    p.memory.registers.dt = 5;
+    p.tick().unwrap();
+    p.end_full_frame();
+    assert_eq!(p.memory.registers.dt, 4);
+}
+
+#[test]
+fn vip_timing() {
+    // ADD V0, 1 (10 cycles) and JP 0x200 (23 cycles)
+    let mut p = processor(Timing::Vip, &[0x7001, 0x1200]);
+    p.run_frame().unwrap();
+    // 80 * 33 = 2640 cycles, which leaves room for starting another ADD
+    assert_eq!(p.cycles, 161);
+    // It overran the frame by 6 cycles, which are missing in the next one: 6 + 23 + 79 * 33 + 10
+    p.run_frame().unwrap();
+    assert_eq!(p.cycles, 161 + 160);
+
+    // Drawing waits for the next frame
+    let mut p = processor(Timing::Vip, &[0xD005, 0x1200]);
+    p.run_frame().unwrap();
+    assert_eq!(p.cycles, 1);
+    p.run_frame().unwrap();
+    assert_eq!(p.cycles, 3);
+}
+
+#[test]
+fn vip_cycles_of_a_program() {
+    let mut p = processor(
+        Timing::Vip,
+        &[
+            0x00E0, // CLS: 24
+            0x6005, // LD V0, 5: 6
+            0x610C, // LD V1, 12: 6
+            0xF029, // LD F, V0: 20
+            0x8014, // ADD V0, V1: 44
+            0xA300, // LD I, 0x300: 12
+            0xF033, // LD B, V0 (17): 48 + 16 * (0 + 1 + 7) = 176
+            0xF265, // LD V2, [I]: 14 + 14 * 3 = 56
+            0x3201, // SE V2, 1: 12
+            0x2216, // CALL 0x216: 23
+            0x1214, // JP 0x214: 23
+            0x00EE, // RET: 23
+        ],
+    );
+    let mut total = 0;
+    for _ in 0..12 {
+        p.tick().unwrap();
+        total += p.frame_cycles;
+        p.frame_cycles = 0;
+    }
+    assert_eq!(p.memory.registers.pc, 0x214);
+    assert_eq!(
+        total,
+        24 + 6 + 6 + 20 + 44 + 12 + 176 + 56 + 12 + 23 + 23 + 23
+    );
+}
+
+#[test]
+fn vip_cycles_depend_on_the_operands() {
+    let mut memory = Memory::default();
+    let drw = Instruction::DRW {
+        reg_x: 0,
+        reg_y: 1,
+        size: 5,
+    };
+    assert_eq!(vip_cycles(&drw, &memory), 68 + 5 * 20);
+    memory.registers.v[0] = 3;
+    assert_eq!(vip_cycles(&drw, &memory), 68 + 5 * (20 + 6 * 3));
+
+    let bcd = Instruction::LDBVx { reg: 0 };
+    memory.registers.v[0] = 100;
+    assert_eq!(vip_cycles(&bcd, &memory), 48 + 16);
+    memory.registers.v[0] = 199;
+    assert_eq!(vip_cycles(&bcd, &memory), 48 + 16 * 19);
+}
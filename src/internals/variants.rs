@@ -0,0 +1,114 @@
+use crate::internals::display::Display;
+use crate::internals::font::Font;
+use crate::internals::memory::{Addressing, Memory, MemoryError};
+use crate::internals::opcode::{InvalidOpcode, Opcode};
+use std::str::FromStr;
+
+pub mod chip8;
+pub mod schip;
+pub mod xochip;
+
+// Everything that differs between the CHIP-8 dialects: Which opcodes exist and how the machine
+// they run on looks like. Each dialect implements this in its own module, usually by handling
+// its extensions and falling back to the dialect it extends for everything else.
+pub trait InstructionSet {
+    fn name(&self) -> &'static str;
+
+    fn decode(&self, opcode: (u8, u8)) -> Box<dyn Opcode>;
+
+    // The size in bytes of the instruction starting with opcode, see Opcode::size
+    fn instruction_size(&self, _opcode: (u8, u8)) -> u16 {
+        2
+    }
+
+    // Decodes an instruction that is longer than a word, operand is the word following opcode
+    fn decode_long(&self, opcode: (u8, u8), _operand: (u8, u8)) -> Box<dyn Opcode> {
+        Box::new(InvalidOpcode { opcode })
+    }
+
+    fn memory_size(&self) -> usize {
+        0x1000
+    }
+
+    // How pc and I wrap around, the 12 bit addresses of the original hardware by default
+    fn addressing(&self) -> Addressing {
+        Addressing::Wrap12
+    }
+
+    // How many subroutine calls can be nested. The COSMAC VIP only had room for 12, but most
+    // interpreters since have 16.
+    fn stack_depth(&self) -> usize {
+        16
+    }
+
+    fn load_fonts(&self, memory: &mut Memory, font: &Font) -> Result<(), MemoryError> {
+        memory.load_font(font)
+    }
+
+    fn display(&self) -> Display {
+        Display::default()
+    }
+
+    // Whether DRW with a size of 0 draws a 16x16 sprite
+    fn big_sprites(&self) -> bool {
+        false
+    }
+
+    // The number of RPL flags available to Fx75/Fx85
+    fn rpl_flags(&self) -> usize {
+        0
+    }
+
+    // The quirks below default to the behaviour of the original COSMAC VIP interpreter
+
+    // Whether 8xy1, 8xy2 and 8xy3 reset VF to 0
+    fn logic_resets_vf(&self) -> bool {
+        true
+    }
+
+    // Whether 8xy6 and 8xyE shift Vy into Vx, instead of shifting Vx in place
+    fn shift_reads_vy(&self) -> bool {
+        true
+    }
+
+    // Whether Fx55 and Fx65 leave I pointing after the last register they stored or loaded
+    fn load_store_increments_i(&self) -> bool {
+        true
+    }
+
+    // Whether Bnnn jumps to nnn + Vx (x being the highest nibble of nnn) instead of nnn + V0
+    fn jump_reads_vx(&self) -> bool {
+        false
+    }
+}
+
+// The dialects known to the command line tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Variant {
+    pub fn instruction_set(self) -> Box<dyn InstructionSet> {
+        match self {
+            Variant::Chip8 => Box::new(chip8::Chip8 {}),
+            Variant::SuperChip => Box::new(schip::SuperChip {}),
+            Variant::XoChip => Box::new(xochip::XoChip {}),
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chip8" => Ok(Variant::Chip8),
+            "schip" => Ok(Variant::SuperChip),
+            "xochip" => Ok(Variant::XoChip),
+            _ => Err(format!("Unknown variant {}", s)),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::internals::font::Font;
+use crate::internals::memory::{Memory, MemoryError};
+use crate::internals::opcode::*;
+use crate::internals::variants::{chip8, InstructionSet};
+
+// SUPER-CHIP 1.1: 128x64 hires mode, scrolling, 16x16 sprites, big font and RPL flags
+pub struct SuperChip {}
+
+impl InstructionSet for SuperChip {
+    fn name(&self) -> &'static str {
+        "schip"
+    }
+
+    fn decode(&self, opcode: (u8, u8)) -> Box<dyn Opcode> {
+        decode(opcode)
+    }
+
+    // Programs written for the HP-48 and later rely on deeper recursion
+    fn stack_depth(&self) -> usize {
+        32
+    }
+
+    fn load_fonts(&self, memory: &mut Memory, font: &Font) -> Result<(), MemoryError> {
+        memory.load_font(font)?;
+        memory.load_big_font(font)
+    }
+
+    fn big_sprites(&self) -> bool {
+        true
+    }
+
+    fn rpl_flags(&self) -> usize {
+        8
+    }
+
+    fn logic_resets_vf(&self) -> bool {
+        false
+    }
+
+    fn shift_reads_vy(&self) -> bool {
+        false
+    }
+
+    fn load_store_increments_i(&self) -> bool {
+        false
+    }
+
+    fn jump_reads_vx(&self) -> bool {
+        true
+    }
+}
+
+pub fn decode(opcode: (u8, u8)) -> Box<dyn Opcode> {
+    let sel = (opcode.0 & 0xF0) >> 4;
+    let val = opcode.0 & 0x0F;
+
+    match (sel, opcode.1) {
+        (0x0, 0xFB) if val == 0 => Box::new(SCR {}),
+        (0x0, 0xFC) if val == 0 => Box::new(SCL {}),
+        (0x0, 0xFD) if val == 0 => Box::new(EXIT {}),
+        (0x0, 0xFE) if val == 0 => Box::new(LOW {}),
+        (0x0, 0xFF) if val == 0 => Box::new(HIGH {}),
+        (0x0, low) if val == 0 && low & 0xF0 == 0xC0 => Box::new(SCD { rows: low & 0x0F }),
+        (0xF, 0x30) => Box::new(LDHFVx { reg: val }),
+        (0xF, 0x75) => Box::new(LDRVx { reg: val }),
+        (0xF, 0x85) => Box::new(LDVxR { reg: val }),
+        _ => chip8::decode(opcode),
+    }
+}
@@ -0,0 +1,81 @@
+use crate::internals::font::Font;
+use crate::internals::memory::{Addressing, Memory, MemoryError};
+use crate::internals::opcode::*;
+use crate::internals::variants::{schip, InstructionSet};
+
+// XO-CHIP: A superset of SUPER-CHIP with 64 KiB RAM, two bitplanes and audio patterns
+pub struct XoChip {}
+
+impl InstructionSet for XoChip {
+    fn name(&self) -> &'static str {
+        "xochip"
+    }
+
+    fn decode(&self, opcode: (u8, u8)) -> Box<dyn Opcode> {
+        decode(opcode)
+    }
+
+    fn instruction_size(&self, opcode: (u8, u8)) -> u16 {
+        if opcode == (0xF0, 0x00) {
+            4
+        } else {
+            2
+        }
+    }
+
+    fn decode_long(&self, opcode: (u8, u8), operand: (u8, u8)) -> Box<dyn Opcode> {
+        match opcode {
+            (0xF0, 0x00) => Box::new(LDILong::new(operand.0, operand.1)),
+            _ => Box::new(InvalidOpcode { opcode }),
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        0x10000
+    }
+
+    fn addressing(&self) -> Addressing {
+        Addressing::Extended
+    }
+
+    fn stack_depth(&self) -> usize {
+        32
+    }
+
+    fn load_fonts(&self, memory: &mut Memory, font: &Font) -> Result<(), MemoryError> {
+        memory.load_font(font)?;
+        memory.load_big_font(font)
+    }
+
+    fn big_sprites(&self) -> bool {
+        true
+    }
+
+    fn rpl_flags(&self) -> usize {
+        16
+    }
+
+    fn logic_resets_vf(&self) -> bool {
+        false
+    }
+}
+
+pub fn decode(opcode: (u8, u8)) -> Box<dyn Opcode> {
+    let sel = (opcode.0 & 0xF0) >> 4;
+    let val = opcode.0 & 0x0F;
+
+    match (sel, opcode.1) {
+        (0x5, low) if low & 0x0F == 2 => Box::new(SAVEVxVy {
+            reg_a: val,
+            reg_b: ((low & 0xF0) >> 4),
+        }),
+        (0x5, low) if low & 0x0F == 3 => Box::new(LOADVxVy {
+            reg_a: val,
+            reg_b: ((low & 0xF0) >> 4),
+        }),
+        (0xF, 0x01) => Box::new(PLANE { planes: val }),
+        (0xF, 0x02) if val == 0 => Box::new(AUDIO {}),
+        (0xF, 0x3A) => Box::new(PITCHVx { reg: val }),
+        _ => schip::decode(opcode),
+    }
+}
@@ -1,10 +1,35 @@
 #[macro_use]
 extern crate downcast_rs;
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
 
 pub mod internals {
+    pub mod assembler;
+    pub mod audio;
+    pub mod disassembler;
     pub mod display;
+    pub mod fault;
+    pub mod font;
+    pub mod instruction;
     pub mod keyboard;
+    pub mod layout;
     pub mod memory;
     pub mod opcode;
     pub mod processor;
+    pub mod random;
+    pub mod replay;
+    pub mod rewind;
+    pub mod savestate;
+    #[cfg(test)]
+    pub mod test_support;
+    pub mod timing;
+    pub mod variants;
+}
+
+pub mod frontend {
+    pub mod recorder;
+    pub mod regression;
+    pub mod terminal;
+    pub mod wav;
 }
@@ -0,0 +1,61 @@
+use std::fs;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Runs emul8 headless on the program with stdin closed, killing it if it doesn't finish in time
+fn run_headless(name: &str, program: &[u8], frames: usize) -> bool {
+    let rom = std::env::temp_dir().join(format!(
+        "emul8-headless-{}-{}.ch8",
+        name,
+        std::process::id()
+    ));
+    fs::write(&rom, program).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_emul8"))
+        .arg(&rom)
+        .args(["--headless", "--frames", &frames.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let start = Instant::now();
+    let finished = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status.success();
+        }
+        if start.elapsed() > Duration::from_secs(10) {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            break false;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    fs::remove_file(&rom).unwrap();
+    finished
+}
+
+#[test]
+fn waiting_for_a_key_doesnt_read_stdin() {
+    // LD V0, K and jump back to it
+    assert!(run_headless("wait", &[0xF0, 0x0A, 0x12, 0x00], 2));
+}
+
+#[test]
+fn vip_layout_needs_chip8() {
+    let rom = std::env::temp_dir().join(format!("emul8-layout-{}.ch8", std::process::id()));
+    fs::write(&rom, [0x12, 0x00]).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_emul8"))
+        .arg(&rom)
+        .args(["--headless", "--frames", "1", "--layout", "vip"])
+        .args(["--variant", "schip"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    fs::remove_file(&rom).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "The VIP memory layout is only available for --variant chip8, not schip\n"
+    );
+}